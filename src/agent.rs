@@ -1,31 +1,31 @@
 use crate::{utils,message,context};
+use context::Position;
 use std::{fmt,cell};
 
-#[derive(Clone)]
-pub struct Agent<I,M,K,T> {
+pub struct Agent<I,M,K,S: context::Space> {
     comm: message::MessageInterface<I,M>,
-    position: utils::Cell<context::GridPosition<T>>,
+    position: utils::Cell<S::Position>,
     kind: K,
     id: I,
 }
 
-impl<I,M,K,T> Agent<I,M,K,T> {
-    pub fn new(comm: message::MessageInterface<I,M>, position: utils::Cell<context::GridPosition<T>>, kind: K, id: I, ) -> Self {
+impl<I,M,K,S: context::Space> Agent<I,M,K,S> {
+    pub fn new(comm: message::MessageInterface<I,M>, position: utils::Cell<S::Position>, kind: K, id: I, ) -> Self {
         Self {comm, position, kind, id}
     }
-    
+
     #[inline]
-    pub fn as_tile(&self) -> cell::Ref<context::GridPosition<T>> {
+    pub fn as_tile(&self) -> cell::Ref<S::Position> {
         self.position.borrow()
     }
 
     #[inline]
-    pub fn as_grid(&self) -> utils::Cell<context::grid::Grid<T>> {
-        self.position.borrow().get_grid()
+    pub fn as_space(&self) -> utils::Cell<S> {
+        self.position.borrow().get_space()
     }
 
     #[inline]
-    pub fn swap_stored_position_with(&self, new: context::GridPosition<T>) {
+    pub fn swap_stored_position_with(&self, new: S::Position) {
         *self.position.borrow_mut() = new;
     }
 
@@ -42,20 +42,37 @@ impl<I,M,K,T> Agent<I,M,K,T> {
     #[inline]
     pub fn get_id(&self) -> &I {
         &self.id
-    }    
+    }
+}
+
+// Derived Clone would demand `S: Clone`, but only the shared position handle is cloned.
+impl<I,M,K,S: context::Space> Clone for Agent<I,M,K,S>
+where
+    I: Clone,
+    M: Clone,
+    K: Clone
+{
+    fn clone(&self) -> Self {
+        Self {
+            comm: self.comm.clone(),
+            position: self.position.clone(),
+            kind: self.kind.clone(),
+            id: self.id.clone()
+        }
+    }
 }
 
-impl<I,M,K,T> fmt::Debug for Agent<I,M,K,T> 
+impl<I,M,K,S: context::Space> fmt::Debug for Agent<I,M,K,S>
 where
     I: fmt::Debug,
     M: fmt::Debug,
     K: fmt::Debug,
-    T: fmt::Debug
+    S::Position: fmt::Debug
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
-            f, 
-            "Id: {:?} \nKind: {:?} \nPosition: {:?}", 
+            f,
+            "Id: {:?} \nKind: {:?} \nPosition: {:?}",
             self.get_id(),
             self.as_kind(),
             self.as_tile()
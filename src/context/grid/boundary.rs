@@ -0,0 +1,124 @@
+use super::PointIndex;
+
+/// What lies beyond the edges of a grid.
+#[derive(Debug,Default,Clone,Copy,PartialEq)]
+pub enum Boundary {
+    /// Edges wrap around onto the opposite side.
+    #[default]
+    Toroidal,
+    /// Edges are walls; points on them simply have fewer neighbours.
+    Closed
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+}
+
+impl Boundary {
+    /// The index one step from `idx` in `direction` on a `row` by `col` grid, if there is one.
+    pub fn step(self, idx: &PointIndex, direction: Direction, (row,col): (usize,usize)) -> Option<PointIndex> 
+    {
+        let max_row: usize = row-1;
+        let max_col: usize = col-1;
+
+        let (new_row, new_col): (usize,usize) = match (direction, self) {
+            (Direction::North, _) if idx.row > 0 => (idx.row - 1, idx.col),
+            (Direction::North, Boundary::Toroidal) => (max_row, idx.col),
+            (Direction::South, _) if idx.row < max_row => (idx.row + 1, idx.col),
+            (Direction::South, Boundary::Toroidal) => (0, idx.col),
+            (Direction::West, _) if idx.col > 0 => (idx.row, idx.col - 1),
+            (Direction::West, Boundary::Toroidal) => (idx.row, max_col),
+            (Direction::East, _) if idx.col < max_col => (idx.row, idx.col + 1),
+            (Direction::East, Boundary::Toroidal) => (idx.row, 0),
+            _ => return None
+        };
+
+        Some(PointIndex::new(new_row, new_col))
+    }
+
+    /// The coordinate `offset` away from `coord` on an axis of length `bound`, if there is one.
+    pub fn shift(self, coord: usize, offset: isize, bound: usize) -> Option<usize> {
+        let shifted: isize = coord as isize + offset;
+        match self {
+            _ if shifted >= 0 && shifted < bound as isize => Some(shifted as usize),
+            Boundary::Toroidal => Some(shifted.rem_euclid(bound as isize) as usize),
+            Boundary::Closed => None
+        }
+    }
+
+    /// The separation of two coordinates on an axis of length `bound`, taking the shorter 
+    /// way around when the axis wraps.
+    pub fn delta(self, lhs: usize, rhs: usize, bound: usize) -> usize {
+        let direct: usize = if lhs > rhs { lhs - rhs } else { rhs - lhs };
+        match self {
+            Boundary::Toroidal => usize::min(direct, bound - direct),
+            Boundary::Closed => direct
+        }
+    }
+
+    /// The signed offset from `lhs` to `rhs` on an axis of length `bound`, going the shorter 
+    /// way around when the axis wraps.
+    pub fn offset(self, lhs: usize, rhs: usize, bound: usize) -> isize {
+        let direct: isize = rhs as isize - lhs as isize;
+        match self {
+            Boundary::Toroidal if direct.abs() as usize > self.delta(lhs, rhs, bound) => direct - direct.signum() * bound as isize,
+            _ => direct
+        }
+    }
+
+    pub fn neighbours(self, idx: &PointIndex, dimensions: (usize,usize)) -> [Option<PointIndex>; 4] {
+        let [north, east, south, west]: [Direction; 4] = Direction::ALL;
+        [
+            self.step(idx, north, dimensions),
+            self.step(idx, east, dimensions),
+            self.step(idx, south, dimensions),
+            self.step(idx, west, dimensions)
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn test_toroidal_step() {
+        let idx: PointIndex = PointIndex::new(0,4);
+        assert_eq!(Boundary::Toroidal.step(&idx, Direction::North, (5,5)), Some(PointIndex::new(4,4)));
+        assert_eq!(Boundary::Toroidal.step(&idx, Direction::East, (5,5)), Some(PointIndex::new(0,0)));
+    }
+
+    #[test]
+    fn test_delta_wraps_both_ways() {
+        assert_eq!(Boundary::Toroidal.delta(1, 9, 10), 2);
+        assert_eq!(Boundary::Toroidal.delta(9, 1, 10), 2);
+        assert_eq!(Boundary::Toroidal.delta(2, 7, 10), 5);
+        assert_eq!(Boundary::Closed.delta(9, 1, 10), 8);
+    }
+
+    #[test]
+    fn test_closed_step() {
+        let idx: PointIndex = PointIndex::new(0,4);
+        assert_eq!(Boundary::Closed.step(&idx, Direction::North, (5,5)), None);
+        assert_eq!(Boundary::Closed.step(&idx, Direction::East, (5,5)), None);
+        assert_eq!(Boundary::Closed.step(&idx, Direction::South, (5,5)), Some(PointIndex::new(1,4)));
+        assert_eq!(Boundary::Closed.step(&idx, Direction::West, (5,5)), Some(PointIndex::new(0,3)));
+    }
+
+    #[test]
+    fn test_shift() {
+        assert_eq!(Boundary::Toroidal.shift(0, -1, 4), Some(3));
+        assert_eq!(Boundary::Toroidal.shift(3, 1, 4), Some(0));
+        assert_eq!(Boundary::Closed.shift(0, -1, 4), None);
+        assert_eq!(Boundary::Closed.shift(2, 1, 4), Some(3));
+    }
+}
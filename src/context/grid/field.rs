@@ -0,0 +1,330 @@
+use super::{PointIndex, PointMap};
+
+use std::{collections,fmt};
+
+/// The value a field layer holds at a single point.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum FieldValue {
+    Scalar(f64),
+    Vector(f64,f64)
+}
+
+impl FieldValue {
+    pub fn as_scalar(&self) -> Option<f64> {
+        match self {
+            FieldValue::Scalar(inner) => Some(*inner),
+            _ => None
+        }
+    }
+
+    pub fn as_vector(&self) -> Option<(f64,f64)> {
+        match self {
+            FieldValue::Vector(x,y) => Some((*x,*y)),
+            _ => None
+        }
+    }
+
+    /// Applies `f` to every component of the value.
+    pub fn map<F: Fn(f64) -> f64>(self, f: F) -> Self {
+        match self {
+            FieldValue::Scalar(inner) => FieldValue::Scalar(f(inner)),
+            FieldValue::Vector(x,y) => FieldValue::Vector(f(x), f(y))
+        }
+    }
+
+    /// Shrinks the value towards zero by `amount`: scalars stop at zero, vectors lose length but keep their direction.
+    pub fn evaporate(self, amount: f64) -> Self {
+        match self {
+            FieldValue::Scalar(inner) => FieldValue::Scalar(f64::max(inner - amount, 0.0)),
+            FieldValue::Vector(x,y) => {
+                let length: f64 = x.hypot(y);
+                if length <= amount { FieldValue::Vector(0.0, 0.0) }
+                else { self.map(|component| component * (length - amount) / length) }
+            }
+        }
+    }
+
+    /// Combines matching components of two values, leaving `self` untouched if their kinds differ.
+    pub fn combine<F: Fn(f64,f64) -> f64>(self, other: Self, f: F) -> Self {
+        match (self, other) {
+            (FieldValue::Scalar(lhs), FieldValue::Scalar(rhs)) => FieldValue::Scalar(f(lhs,rhs)),
+            (FieldValue::Vector(x1,y1), FieldValue::Vector(x2,y2)) => FieldValue::Vector(f(x1,x2), f(y1,y2)),
+            _ => self
+        }
+    }
+}
+
+/// Built-in per-tick dynamics for field layers, applied component-wise.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Dynamics {
+    /// Spreads each value toward its neighbours by the discrete Laplacian scaled by the rate,
+    /// which must lie in [0, 0.25]; anything above is unstable on a four-point neighbourhood.
+    Diffusion(f64),
+    /// Loses the given fraction of each value per tick, which must lie in [0, 1].
+    Decay(f64),
+    /// Loses a fixed amount of each value per tick, stopping at zero; vectors shrink along their direction.
+    Evaporation(f64),
+    /// Gains a fixed amount per tick, stopping at the ceiling.
+    Regrowth { rate: f64, ceiling: f64 }
+}
+
+impl Dynamics {
+    /// Panics if `rate` lies outside [0, 0.25].
+    pub fn diffusion(rate: f64) -> Self {
+        Dynamics::Diffusion(rate).checked()
+    }
+
+    /// Panics if `rate` lies outside [0, 1].
+    pub fn decay(rate: f64) -> Self {
+        Dynamics::Decay(rate).checked()
+    }
+
+    pub(super) fn checked(self) -> Self {
+        match self {
+            Dynamics::Diffusion(rate) => assert!((0.0..=0.25).contains(&rate), "diffusion rate must lie in [0, 0.25], got {}", rate),
+            Dynamics::Decay(rate) => assert!((0.0..=1.0).contains(&rate), "decay rate must lie in [0, 1], got {}", rate),
+            _ => { }
+        }
+        self
+    }
+
+    fn apply<N>(&self, field: &mut Field, (_,col): (usize,usize), neighbours: &N) 
+    where
+        N: Fn(&PointIndex) -> [Option<PointIndex>; 4]
+    {
+        match *self {
+            Dynamics::Diffusion(rate) => {
+                let spread: _ = |flat_idx: usize| -> Vec<usize> {
+                    neighbours(&PointIndex::new(flat_idx / col, flat_idx % col))
+                        .iter()
+                        .filter_map(|neighbour| neighbour.as_ref())
+                        .map(|neighbour| neighbour.row * col + neighbour.col)
+                        .collect()
+                };
+                let diffused: Vec<(usize, FieldValue)> = field.values
+                    .reach(&spread)
+                    .into_iter()
+                    .map(|flat_idx| {
+                        let value: FieldValue = field.get(flat_idx);
+                        let diffused_value: FieldValue = spread(flat_idx)
+                            .into_iter()
+                            .fold(value, |acc, neighbour| {
+                                let flux: FieldValue = field.get(neighbour).combine(value, |o, v| rate * (o - v));
+                                acc.combine(flux, |a, f| a + f)
+                            });
+                        (flat_idx, diffused_value)
+                    }).collect();
+
+                for (flat_idx, value) in diffused.into_iter() {
+                    field.set(flat_idx, value);
+                }
+            },
+            Dynamics::Decay(rate) => field.map_in_place(|value| value * (1.0 - rate)),
+            Dynamics::Evaporation(amount) => field.values.map_in_place(|value| value.evaporate(amount)),
+            Dynamics::Regrowth { rate, ceiling } => field.map_in_place(|value| f64::min(value + rate, ceiling))
+        }
+    }
+}
+
+/// A single named layer, storing one value per point in row-major order.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Field {
+    values: PointMap<FieldValue>
+}
+
+impl Field {
+    pub(super) fn new(values: PointMap<FieldValue>) -> Self {
+        Self { values }
+    }
+
+    pub fn get(&self, flat_idx: usize) -> FieldValue {
+        self.values.get(flat_idx)
+    }
+
+    pub fn set(&mut self, flat_idx: usize, value: FieldValue) -> FieldValue {
+        self.values.set(flat_idx, value)
+    }
+
+    /// The stored values by flat index; on a sparse grid, points still at the initial value are skipped.
+    pub fn iter(&self) -> Box<dyn Iterator<Item=(usize, &FieldValue)> + '_> {
+        self.values.iter()
+    }
+
+    fn map_in_place<F: Fn(f64) -> f64>(&mut self, f: F) {
+        self.values.map_in_place(|value| value.map(&f))
+    }
+}
+
+type FieldHook = Box<dyn FnMut(&PointIndex, &mut FieldValue)>;
+
+/// The field layers of a grid and the hooks run over them on every tick.
+///
+/// On a sparse grid, hooks only see points whose value has left the layer's initial value.
+#[derive(Default)]
+pub struct Fields {
+    layers: collections::HashMap<String, Field>,
+    hooks: Vec<(String, FieldHook)>,
+    dynamics: Vec<(String, Dynamics)>
+}
+
+impl Fields {
+    pub(super) fn insert(&mut self, name: String, field: Field) -> Option<Field> {
+        self.layers.insert(name, field)
+    }
+
+    pub(super) fn get(&self, name: &str) -> Option<&Field> {
+        self.layers.get(name)
+    }
+
+    pub(super) fn get_mut(&mut self, name: &str) -> Option<&mut Field> {
+        self.layers.get_mut(name)
+    }
+
+    pub(super) fn push_hook(&mut self, name: String, hook: FieldHook) {
+        self.hooks.push((name, hook))
+    }
+
+    pub(super) fn push_dynamics(&mut self, name: String, dynamics: Dynamics) {
+        self.dynamics.push((name, dynamics))
+    }
+
+    pub(super) fn update<N>(&mut self, dimensions: (usize,usize), neighbours: N) 
+    where
+        N: Fn(&PointIndex) -> [Option<PointIndex>; 4]
+    {
+        let (_,col): (usize,usize) = dimensions;
+        let layers: &mut collections::HashMap<_,_> = &mut self.layers;
+
+        for (name, hook) in self.hooks.iter_mut() 
+        {
+            if let Some(field) = layers.get_mut(name) {
+                for (flat_idx, value) in field.values.iter_mut() 
+                {
+                    let index: PointIndex = PointIndex::new(flat_idx / col, flat_idx % col);
+                    hook(&index, value)
+                }
+            }
+        }
+
+        for (name, dynamics) in self.dynamics.iter() 
+        {
+            if let Some(field) = layers.get_mut(name) {
+                dynamics.apply(field, dimensions, &neighbours)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Fields {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.layers.keys().collect();
+        write!(f, "Fields: {:?}, Hooks: {}, Dynamics: {:?}", names, self.hooks.len(), self.dynamics)
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use super::super::{Grid, Boundary};
+
+    fn scalar_at(grid: &Grid<()>, row: usize, col: usize) -> f64 {
+        grid.read_field("scent", &PointIndex::new(row,col))
+            .and_then(|value| value.as_scalar())
+            .unwrap()
+    }
+
+    fn total(grid: &Grid<()>) -> f64 {
+        grid.get_field("scent")
+            .unwrap()
+            .iter()
+            .filter_map(|(_, value)| value.as_scalar())
+            .sum()
+    }
+
+    #[test]
+    fn test_diffusion_conserves_mass() 
+    {
+        for boundary in [Boundary::Toroidal, Boundary::Closed].iter() {
+            let mut grid: Grid<()> = Grid::new(4,6).with_boundary(*boundary);
+            grid.add_field("scent", FieldValue::Scalar(0.0));
+            grid.write_field("scent", &PointIndex::new(0,0), FieldValue::Scalar(8.0));
+            grid.add_dynamics("scent", Dynamics::Diffusion(0.25));
+
+            grid.update_fields();
+
+            assert!((total(&grid) - 8.0).abs() < 1e-9);
+            assert!((scalar_at(&grid, 0,1) - 2.0).abs() < 1e-9);
+            match boundary {
+                Boundary::Toroidal => {
+                    assert!((scalar_at(&grid, 0,0)).abs() < 1e-9);
+                    assert!((scalar_at(&grid, 3,0) - 2.0).abs() < 1e-9);
+                },
+                Boundary::Closed => {
+                    assert!((scalar_at(&grid, 0,0) - 4.0).abs() < 1e-9);
+                    assert!((scalar_at(&grid, 3,0)).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_decay_and_evaporation() 
+    {
+        let mut grid: Grid<()> = Grid::new(3,3);
+        grid.add_field("scent", FieldValue::Scalar(1.0));
+        grid.add_dynamics("scent", Dynamics::Decay(0.5));
+        grid.add_dynamics("scent", Dynamics::Evaporation(0.2));
+
+        grid.update_fields();
+        assert!((scalar_at(&grid, 1,1) - 0.3).abs() < 1e-9);
+
+        grid.update_fields();
+        assert_eq!(scalar_at(&grid, 1,1), 0.0);
+    }
+
+    #[test]
+    fn test_vector_evaporation_keeps_direction() 
+    {
+        let mut grid: Grid<()> = Grid::new(3,3);
+        grid.add_field("wind", FieldValue::Vector(3.0, -4.0));
+        grid.add_dynamics("wind", Dynamics::Evaporation(2.5));
+
+        grid.update_fields();
+        let (x,y): (f64,f64) = grid.read_field("wind", &PointIndex::new(1,1)).and_then(|value| value.as_vector()).unwrap();
+        assert!((x - 1.5).abs() < 1e-9 && (y + 2.0).abs() < 1e-9);
+
+        grid.update_fields();
+        assert_eq!(grid.read_field("wind", &PointIndex::new(1,1)), Some(FieldValue::Vector(0.0, 0.0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "diffusion rate must lie in [0, 0.25], got 0.3")]
+    fn test_unstable_diffusion_is_rejected() 
+    {
+        let mut grid: Grid<()> = Grid::new(3,3);
+        grid.add_field("scent", FieldValue::Scalar(0.0));
+        grid.add_dynamics("scent", Dynamics::Diffusion(0.3));
+    }
+
+    #[test]
+    #[should_panic(expected = "decay rate must lie in [0, 1], got NaN")]
+    fn test_nan_decay_is_rejected() 
+    {
+        let _: Dynamics = Dynamics::decay(f64::NAN);
+    }
+
+    #[test]
+    fn test_bounded_regrowth() 
+    {
+        let mut grid: Grid<()> = Grid::new(3,3);
+        grid.add_field("scent", FieldValue::Scalar(0.0));
+        grid.add_dynamics("scent", Dynamics::Regrowth { rate: 0.4, ceiling: 1.0 });
+
+        for _ in 0..3 {
+            grid.update_fields();
+        }
+        assert_eq!(scalar_at(&grid, 2,2), 1.0);
+    }
+}
@@ -0,0 +1,349 @@
+use super::{Grid, PointIndex, PointMap, Boundary, Direction, Obstacles};
+
+use std::collections;
+
+/// Step distances from every point to the nearest of a set of goals.
+#[derive(Debug,Clone,PartialEq)]
+pub struct DistanceMap {
+    dimensions: (usize,usize),
+    distances: PointMap<Option<usize>>
+}
+
+impl DistanceMap {
+    fn unreached<T>(grid: &Grid<T>) -> Self {
+        Self { dimensions: grid.get_dimensions(), distances: grid.point_map(None) }
+    }
+
+    pub fn get(&self, idx: &PointIndex) -> Option<usize> {
+        self.distances.get(idx.row * self.dimensions.1 + idx.col)
+    }
+
+    fn set(&mut self, idx: &PointIndex, distance: usize) {
+        self.distances.set(idx.row * self.dimensions.1 + idx.col, Some(distance));
+    }
+
+    fn clear(&mut self, idx: &PointIndex) {
+        self.distances.set(idx.row * self.dimensions.1 + idx.col, None);
+    }
+
+    // Breadth-first relaxation outward from `seeds`, only ever lowering distances.
+    fn relax<T>(&mut self, grid: &Grid<T>, seeds: Vec<PointIndex>, obstacles: Obstacles) 
+    {
+        for seed in seeds.iter() {
+            self.set(seed, 0)
+        }
+        self.spread(grid, seeds, obstacles)
+    }
+
+    // Relaxation outward from points that already hold their distance, nearest first.
+    fn spread<T>(&mut self, grid: &Grid<T>, mut seeds: Vec<PointIndex>, obstacles: Obstacles) 
+    {
+        seeds.sort_by_key(|seed| self.get(seed));
+        let mut frontier: collections::VecDeque<PointIndex> = seeds.into_iter().collect();
+
+        while let Some(current) = frontier.pop_front() 
+        {
+            let next_distance: usize = self.get(&current).expect("expected a distance on the frontier") + 1;
+
+            for neighbour in grid.boundary
+                .neighbours(&current, self.dimensions)
+                .iter()
+                .filter_map(|neighbour| neighbour.clone())
+            {
+                let improves: bool = self.get(&neighbour).map_or(true, |distance| next_distance < distance);
+                if improves && !grid.is_obstacle(&neighbour, obstacles) {
+                    self.set(&neighbour, next_distance);
+                    frontier.push_back(neighbour)
+                }
+            }
+        }
+    }
+
+    // Clears `seeds` and every distance that was only reached through them, returning the 
+    // cleared points. A point keeps its distance while any neighbour one step closer keeps its own.
+    fn invalidate<T>(&mut self, grid: &Grid<T>, seeds: Vec<PointIndex>, goals: &collections::HashSet<PointIndex>) -> Vec<PointIndex> 
+    {
+        let mut cleared: Vec<PointIndex> = Vec::new();
+        let mut frontier: collections::VecDeque<(PointIndex, usize)> = collections::VecDeque::new();
+
+        for seed in seeds.into_iter() {
+            if let Some(distance) = self.get(&seed) {
+                self.clear(&seed);
+                cleared.push(seed.clone());
+                frontier.push_back((seed, distance))
+            }
+        }
+
+        while let Some((current, distance)) = frontier.pop_front() 
+        {
+            for neighbour in grid.get_connections(&current) 
+            {
+                if goals.contains(&neighbour) || self.get(&neighbour) != Some(distance + 1) {
+                    continue
+                }
+                let supported: bool = grid
+                    .get_connections(&neighbour)
+                    .iter()
+                    .any(|other| self.get(other) == Some(distance));
+
+                if !supported {
+                    self.clear(&neighbour);
+                    cleared.push(neighbour.clone());
+                    frontier.push_back((neighbour, distance + 1))
+                }
+            }
+        }
+        cleared
+    }
+}
+
+/// A distance map toward a set of goals whose gradient agents can follow downhill.
+///
+/// A flow field added to a grid with `add_flow` hears of every point whose state changes, so 
+/// `refresh` only relaxes the region that the changed points and goals affect. A field kept 
+/// outside the grid cannot tell which points changed, and recomputes once the grid's epoch moves on.
+#[derive(Debug,Clone)]
+pub struct FlowField {
+    boundary: Boundary,
+    obstacles: Obstacles,
+    goals: collections::HashSet<PointIndex>,
+    distances: DistanceMap,
+    epoch: u64,
+    dirty: collections::HashSet<PointIndex>
+}
+
+impl FlowField {
+    pub fn new<T>(grid: &Grid<T>, obstacles: Obstacles) -> Self {
+        Self {
+            boundary: grid.boundary,
+            obstacles,
+            goals: collections::HashSet::new(),
+            distances: DistanceMap::unreached(grid),
+            epoch: grid.get_epoch(),
+            dirty: collections::HashSet::new()
+        }
+    }
+
+    // Notes a change to the state of `idx`, made by the grid as the epoch moved on.
+    pub(super) fn touch(&mut self, idx: &PointIndex, epoch: u64) {
+        self.dirty.insert(idx.clone());
+        self.epoch = epoch;
+    }
+
+    /// Moves the field onto a new set of goals, reusing the previous distances where it can.
+    pub fn refresh<T,I>(&mut self, grid: &Grid<T>, goals: I) 
+    where
+        I: IntoIterator<Item=PointIndex>
+    {
+        let goals: collections::HashSet<PointIndex> = goals.into_iter().collect();
+
+        if self.epoch != grid.get_epoch() {
+            self.goals = goals;
+            return self.recompute(grid)
+        }
+
+        let obstacles: Obstacles = self.obstacles;
+        let (blocked, freed): (Vec<PointIndex>, Vec<PointIndex>) = self.dirty
+            .drain()
+            .filter(|idx| !goals.contains(idx))
+            .partition(|idx| grid.is_obstacle(idx, obstacles));
+        let removed: Vec<PointIndex> = self.goals.difference(&goals)
+            .cloned()
+            .chain(blocked)
+            .collect();
+        let added: Vec<PointIndex> = goals.difference(&self.goals)
+            .cloned()
+            .collect();
+
+        // lost distances are rebuilt from the edge of the region they were cleared from
+        let cleared: Vec<PointIndex> = self.distances.invalidate(grid, removed, &goals);
+        let mut frontier: Vec<PointIndex> = cleared
+            .iter()
+            .chain(freed.iter())
+            .flat_map(|idx| grid.get_connections(idx))
+            .filter(|neighbour| self.distances.get(neighbour).is_some())
+            .collect();
+        for goal in added.into_iter() {
+            self.distances.set(&goal, 0);
+            frontier.push(goal)
+        }
+
+        self.distances.spread(grid, frontier, obstacles);
+        self.goals = goals;
+    }
+
+    pub fn recompute<T>(&mut self, grid: &Grid<T>) {
+        self.distances = DistanceMap::unreached(grid);
+        self.dirty.clear();
+        self.epoch = grid.get_epoch();
+        let seeds: Vec<PointIndex> = self.goals.iter().cloned().collect();
+        self.distances.relax(grid, seeds, self.obstacles)
+    }
+
+    pub fn as_distances(&self) -> &DistanceMap {
+        &self.distances
+    }
+
+    /// The downhill direction from `idx`, or None at a goal or where no goal can be reached.
+    pub fn direction(&self, idx: &PointIndex) -> Option<Direction> 
+    {
+        let own_distance: usize = self.distances.get(idx)?;
+        let dimensions: (usize,usize) = self.distances.dimensions;
+
+        Direction::ALL
+            .iter()
+            .filter_map(|direction| {
+                let neighbour: PointIndex = self.boundary.step(idx, *direction, dimensions)?;
+                let distance: usize = self.distances.get(&neighbour)?;
+                Some((*direction, distance))
+            })
+            .filter(|(_, distance)| *distance < own_distance)
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(direction, _)| direction)
+    }
+
+    pub fn next_step(&self, idx: &PointIndex) -> Option<PointIndex> {
+        let direction: Direction = self.direction(idx)?;
+        self.boundary.step(idx, direction, self.distances.dimensions)
+    }
+}
+
+impl<T> Grid<T> 
+{
+    /// Step distances from every point to the nearest goal, routing around `obstacles`.
+    pub fn distance_map<I>(&self, goals: I, obstacles: Obstacles) -> DistanceMap 
+    where
+        I: IntoIterator<Item=PointIndex>
+    {
+        let mut distances: DistanceMap = DistanceMap::unreached(self);
+        distances.relax(self, goals.into_iter().collect(), obstacles);
+        distances
+    }
+
+    /// Adds a named flow field with no goals, replacing any flow field of the same name.
+    pub fn add_flow<S: Into<String>>(&mut self, name: S, obstacles: Obstacles) -> Option<FlowField> {
+        let flow: FlowField = FlowField::new(self, obstacles);
+        self.flows.insert(name.into(), flow)
+    }
+
+    pub fn get_flow(&self, name: &str) -> Option<&FlowField> {
+        self.flows.get(name)
+    }
+
+    /// Points the named flow field at a new set of goals; call this once per tick.
+    pub fn refresh_flow<I>(&mut self, name: &str, goals: I) 
+    where
+        I: IntoIterator<Item=PointIndex>
+    {
+        if let Some(mut flow) = self.flows.remove(name) {
+            flow.refresh(self, goals);
+            self.flows.insert(name.to_string(), flow);
+        }
+    }
+
+    pub fn flow_step(&self, name: &str, idx: &PointIndex) -> Option<PointIndex> {
+        self.flows
+            .get(name)?
+            .next_step(idx)
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use super::super::PointState;
+
+    #[test]
+    fn test_multi_source_distances() 
+    {
+        let grid: Grid<()> = Grid::new(5,5).with_boundary(Boundary::Closed);
+        let goals: Vec<PointIndex> = vec![PointIndex::new(0,0), PointIndex::new(4,4)];
+
+        let distances: DistanceMap = grid.distance_map(goals, Obstacles::default());
+
+        assert_eq!(distances.get(&PointIndex::new(0,0)), Some(0));
+        assert_eq!(distances.get(&PointIndex::new(1,1)), Some(2));
+        assert_eq!(distances.get(&PointIndex::new(3,4)), Some(1));
+        assert_eq!(distances.get(&PointIndex::new(2,2)), Some(4));
+    }
+
+    #[test]
+    fn test_distances_route_around_obstacles() 
+    {
+        let mut grid: Grid<()> = Grid::new(3,3).with_boundary(Boundary::Closed);
+        grid.replace(&PointIndex::new(0,1), PointState::Occupied(()));
+        grid.replace(&PointIndex::new(1,1), PointState::Occupied(()));
+
+        let obstacles: Obstacles = Obstacles { occupied: true, claimed: true };
+        let distances: DistanceMap = grid.distance_map(vec![PointIndex::new(0,0)], obstacles);
+
+        assert_eq!(distances.get(&PointIndex::new(0,2)), Some(6));
+        assert_eq!(distances.get(&PointIndex::new(1,1)), None);
+    }
+
+    #[test]
+    fn test_flow_leads_to_goal() 
+    {
+        let mut grid: Grid<()> = Grid::new(6,6);
+        grid.add_flow("hunt", Obstacles::default());
+        grid.refresh_flow("hunt", vec![PointIndex::new(3,3)]);
+
+        let mut current: PointIndex = PointIndex::new(0,5);
+        let mut steps: usize = 0;
+        while let Some(next) = grid.flow_step("hunt", &current) {
+            current = next;
+            steps += 1;
+        }
+
+        assert_eq!(current, PointIndex::new(3,3));
+        assert_eq!(steps, 5);
+    }
+
+    #[test]
+    fn test_incremental_refresh_matches_recompute() 
+    {
+        let grid: Grid<()> = Grid::new(8,5);
+        let mut flow: FlowField = FlowField::new(&grid, Obstacles::default());
+
+        flow.refresh(&grid, vec![PointIndex::new(1,1)]);
+        flow.refresh(&grid, vec![PointIndex::new(1,1), PointIndex::new(6,3)]);
+        let expected: DistanceMap = grid.distance_map(vec![PointIndex::new(1,1), PointIndex::new(6,3)], Obstacles::default());
+        assert_eq!(flow.as_distances(), &expected);
+
+        flow.refresh(&grid, vec![PointIndex::new(6,3)]);
+        let expected: DistanceMap = grid.distance_map(vec![PointIndex::new(6,3)], Obstacles::default());
+        assert_eq!(flow.as_distances(), &expected);
+    }
+
+    #[test]
+    fn test_refresh_follows_moving_obstacles() 
+    {
+        let mut grid: Grid<()> = Grid::new(7,7).with_boundary(Boundary::Closed);
+        let obstacles: Obstacles = Obstacles { occupied: true, claimed: true };
+        let goals: Vec<PointIndex> = vec![PointIndex::new(2,0)];
+        grid.add_flow("hunt", obstacles);
+        grid.refresh_flow("hunt", goals.clone());
+
+        // a wall down column 2 with its gap at the bottom, then moved to the top
+        for row in 0..6 {
+            grid.replace(&PointIndex::new(row,2), PointState::Occupied(()));
+        }
+        grid.refresh_flow("hunt", goals.clone());
+        assert_eq!(grid.get_flow("hunt").unwrap().as_distances(), &grid.distance_map(goals.clone(), obstacles));
+        assert_eq!(grid.get_flow("hunt").unwrap().as_distances().get(&PointIndex::new(2,3)), Some(11));
+
+        grid.move_inner_into(&PointIndex::new(0,2), &PointIndex::new(6,2));
+        grid.refresh_flow("hunt", goals.clone());
+        assert_eq!(grid.get_flow("hunt").unwrap().as_distances(), &grid.distance_map(goals.clone(), obstacles));
+        assert_eq!(grid.get_flow("hunt").unwrap().as_distances().get(&PointIndex::new(2,3)), Some(7));
+
+        // a field kept outside the grid falls back to a full recompute
+        let mut flow: FlowField = FlowField::new(&grid, obstacles);
+        flow.refresh(&grid, goals.clone());
+        grid.move_inner_into(&PointIndex::new(3,2), &PointIndex::new(0,2));
+        flow.refresh(&grid, goals.clone());
+        assert_eq!(flow.as_distances(), &grid.distance_map(goals, obstacles));
+    }
+}
@@ -0,0 +1,411 @@
+use super::{Grid, PointIndex, FieldValue, Terrain};
+
+use std::{collections, error, fmt, fs, io, path};
+
+/// A grid built from a map, along with the spawn zones marked on it.
+#[derive(Debug)]
+pub struct GridMap<T> {
+    grid: Grid<T>,
+    zones: collections::HashMap<char, Vec<PointIndex>>
+}
+
+impl<T> GridMap<T>
+{
+    pub fn get_grid(&self) -> &Grid<T> {
+        &self.grid
+    }
+
+    pub fn into_grid(self) -> Grid<T> {
+        self.grid
+    }
+
+    /// The points marked with `label`, in row-major order.
+    pub fn zone(&self, label: char) -> Option<&[PointIndex]> {
+        self.zones
+            .get(&label)
+            .map(|points| points.as_slice())
+    }
+
+    pub fn zones(&self) -> &collections::HashMap<char, Vec<PointIndex>> {
+        &self.zones
+    }
+
+    pub fn into_parts(self) -> (Grid<T>, collections::HashMap<char, Vec<PointIndex>>) {
+        (self.grid, self.zones)
+    }
+}
+
+/// What a single pixel of a bitmap map lays down at its point.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Paint {
+    Terrain(Terrain),
+    /// Writes the value into the named layer, adding the layer at zero if it is missing.
+    Field(String, FieldValue),
+    Zone(char)
+}
+
+impl Paint {
+    /// Black is impassable, white is open and anything between is rougher the darker it is.
+    pub fn by_shade((r,g,b): (u8,u8,u8)) -> Vec<Paint> {
+        let shade: u16 = (r as u16 + g as u16 + b as u16) / 3;
+        let terrain: Terrain = match shade {
+            0..=31 => Terrain::Impassable,
+            255 => Terrain::Open,
+            shade => Terrain::Rough(1.0 + (255 - shade) as f64 / 32.0)
+        };
+        vec![Paint::Terrain(terrain)]
+    }
+}
+
+impl<T> Grid<T>
+{
+    /// Builds a grid from a text map: `#` is a wall, `.` is floor and any letter is floor
+    /// belonging to the spawn zone of that letter. Every line must be the same length.
+    pub fn from_ascii(text: &str) -> Result<GridMap<T>, MapError>
+    {
+        let lines: Vec<&str> = text.lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| !line.is_empty())
+            .collect();
+        let col: usize = lines.first().ok_or(MapErrorKind::Empty)?.chars().count();
+
+        let mut grid: Grid<T> = Grid::new(lines.len(), col);
+        let mut zones: collections::HashMap<char, Vec<PointIndex>> = collections::HashMap::new();
+
+        for (row, line) in lines.iter().enumerate()
+        {
+            let found: usize = line.chars().count();
+            if found != col {
+                return Err(MapErrorKind::Ragged { line: row + 1, expected: col, found }.into())
+            }
+            for (col, tile) in line.chars().enumerate() {
+                let idx: PointIndex = PointIndex::new(row, col);
+                match tile {
+                    '#' => { grid.set_terrain(&idx, Terrain::Impassable); },
+                    '.' => { },
+                    label if label.is_alphabetic() => zones.entry(label).or_insert_with(Vec::new).push(idx),
+                    _ => return Err(MapErrorKind::UnknownTile { line: row + 1, col: col + 1, tile }.into())
+                }
+            }
+        }
+        Ok(GridMap { grid, zones })
+    }
+
+    pub fn load_ascii<P: AsRef<path::Path>>(path: P) -> Result<GridMap<T>, MapError> {
+        let text: String = fs::read_to_string(path)?;
+        Self::from_ascii(&text)
+    }
+
+    /// Builds a grid from a binary (`P5`) or plain (`P2`) PGM image, one point per pixel; 
+    /// the palette sees each shade repeated across all three channels.
+    pub fn from_pgm<F>(bytes: &[u8], palette: F) -> Result<GridMap<T>, MapError>
+    where
+        F: Fn((u8,u8,u8)) -> Vec<Paint>
+    {
+        let (width, height, pixels): (usize, usize, Vec<u8>) = pgm::decode(bytes)?;
+        let pixels: Vec<(u8,u8,u8)> = pixels.into_iter()
+            .map(|shade| (shade, shade, shade))
+            .collect();
+        Ok(Self::paint(height, width, &pixels, palette))
+    }
+
+    pub fn load_pgm<P,F>(path: P, palette: F) -> Result<GridMap<T>, MapError>
+    where
+        P: AsRef<path::Path>,
+        F: Fn((u8,u8,u8)) -> Vec<Paint>
+    {
+        let bytes: Vec<u8> = fs::read(path)?;
+        Self::from_pgm(&bytes, palette)
+    }
+
+    /// Builds a grid from a PNG image of any colour type, one point per pixel.
+    #[cfg(feature = "png")]
+    pub fn from_png<R,F>(reader: R, palette: F) -> Result<GridMap<T>, MapError>
+    where
+        R: io::Read,
+        F: Fn((u8,u8,u8)) -> Vec<Paint>
+    {
+        let mut decoder: png::Decoder<R> = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+        let (info, mut reader): (png::OutputInfo, png::Reader<R>) = decoder.read_info()?;
+        let mut buffer: Vec<u8> = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut buffer)?;
+
+        let channels: usize = reader.output_color_type().0.samples();
+        let pixels: Vec<(u8,u8,u8)> = buffer
+            .chunks(channels)
+            .take(info.width as usize * info.height as usize)
+            .map(|pixel| match pixel.len() {
+                1 | 2 => (pixel[0], pixel[0], pixel[0]),
+                _ => (pixel[0], pixel[1], pixel[2])
+            })
+            .collect();
+        Ok(Self::paint(info.height as usize, info.width as usize, &pixels, palette))
+    }
+
+    #[cfg(feature = "png")]
+    pub fn load_png<P,F>(path: P, palette: F) -> Result<GridMap<T>, MapError>
+    where
+        P: AsRef<path::Path>,
+        F: Fn((u8,u8,u8)) -> Vec<Paint>
+    {
+        let file: fs::File = fs::File::open(path)?;
+        Self::from_png(io::BufReader::new(file), palette)
+    }
+
+    fn paint<F>(row: usize, col: usize, pixels: &[(u8,u8,u8)], palette: F) -> GridMap<T>
+    where
+        F: Fn((u8,u8,u8)) -> Vec<Paint>
+    {
+        let mut grid: Grid<T> = Grid::new(row, col);
+        let mut zones: collections::HashMap<char, Vec<PointIndex>> = collections::HashMap::new();
+
+        for (flat_idx, pixel) in pixels.iter().enumerate() {
+            let idx: PointIndex = PointIndex::new(flat_idx / col, flat_idx % col);
+            for paint in palette(*pixel).into_iter() {
+                match paint {
+                    Paint::Terrain(terrain) => { grid.set_terrain(&idx, terrain); },
+                    Paint::Field(name, value) => {
+                        if grid.get_field(&name).is_none() {
+                            grid.add_field(name.clone(), value.map(|_| 0.0));
+                        }
+                        grid.write_field(&name, &idx, value);
+                    },
+                    Paint::Zone(label) => zones.entry(label).or_insert_with(Vec::new).push(idx.clone())
+                }
+            }
+        }
+        GridMap { grid, zones }
+    }
+}
+
+mod pgm
+{
+    use super::{MapError, MapErrorKind};
+
+    // The next whitespace-separated header token, skipping `#` comments.
+    fn token<'a>(bytes: &'a [u8], cursor: &mut usize) -> Result<&'a [u8], MapError>
+    {
+        loop {
+            while *cursor < bytes.len() && bytes[*cursor].is_ascii_whitespace() {
+                *cursor += 1
+            }
+            if *cursor < bytes.len() && bytes[*cursor] == b'#' {
+                while *cursor < bytes.len() && bytes[*cursor] != b'\n' {
+                    *cursor += 1
+                }
+            } else {
+                break
+            }
+        }
+        let start: usize = *cursor;
+        while *cursor < bytes.len() && !bytes[*cursor].is_ascii_whitespace() {
+            *cursor += 1
+        }
+        if start == *cursor {
+            return Err(MapErrorKind::Malformed("the image ended early").into())
+        }
+        Ok(&bytes[start..*cursor])
+    }
+
+    fn number(bytes: &[u8], cursor: &mut usize) -> Result<usize, MapError> {
+        std::str::from_utf8(token(bytes, cursor)?)
+            .ok()
+            .and_then(|text| text.parse().ok())
+            .ok_or_else(|| MapErrorKind::Malformed("expected a number in the image").into())
+    }
+
+    /// The width, height and 8-bit shades of a PGM image.
+    pub(super) fn decode(bytes: &[u8]) -> Result<(usize, usize, Vec<u8>), MapError>
+    {
+        let mut cursor: usize = 0;
+        let magic: &[u8] = token(bytes, &mut cursor)?;
+        let width: usize = number(bytes, &mut cursor)?;
+        let height: usize = number(bytes, &mut cursor)?;
+        let max_value: usize = number(bytes, &mut cursor)?;
+
+        if max_value == 0 || max_value > 65535 {
+            return Err(MapErrorKind::Malformed("the maximum shade must be between 1 and 65535").into())
+        }
+        let scale: _ = |value: usize| (usize::min(value, max_value) * 255 / max_value) as u8;
+
+        let shades: Vec<u8> = match magic {
+            b"P2" => (0..width * height)
+                .map(|_| number(bytes, &mut cursor).map(scale))
+                .collect::<Result<_,_>>()?,
+            b"P5" => {
+                // a single whitespace byte separates the header from the raster
+                let raster: &[u8] = bytes.get(cursor + 1..).unwrap_or(&[]);
+                let sample: usize = if max_value < 256 { 1 } else { 2 };
+                if raster.len() < width * height * sample {
+                    return Err(MapErrorKind::Malformed("the image ended early").into())
+                }
+                raster.chunks(sample)
+                    .take(width * height)
+                    .map(|chunk| scale(chunk.iter().fold(0, |acc, byte| acc * 256 + *byte as usize)))
+                    .collect()
+            },
+            _ => return Err(MapErrorKind::Malformed("expected a P2 or P5 header").into())
+        };
+        Ok((width, height, shades))
+    }
+}
+
+#[derive(Debug)]
+pub struct MapError {
+    kind: MapErrorKind
+}
+
+impl MapError {
+    pub fn kind(&self) -> &MapErrorKind {
+        &self.kind
+    }
+}
+
+impl error::Error for MapError { }
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            MapErrorKind::Io(error) => write!(f, "could not read the map: {}", error),
+            MapErrorKind::Empty => write!(f, "the map has no rows"),
+            MapErrorKind::Ragged { line, expected, found } => write!(f, "line {} has {} tiles but the map is {} wide", line, found, expected),
+            MapErrorKind::UnknownTile { line, col, tile } => write!(f, "unknown tile {:?} at line {}, column {}", tile, line, col),
+            MapErrorKind::Malformed(reason) => write!(f, "malformed image: {}", reason),
+            #[cfg(feature = "png")]
+            MapErrorKind::Png(error) => write!(f, "could not decode the PNG: {}", error)
+        }
+    }
+}
+
+impl From<MapErrorKind> for MapError {
+    fn from(kind: MapErrorKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl From<io::Error> for MapError {
+    fn from(error: io::Error) -> Self {
+        MapErrorKind::Io(error).into()
+    }
+}
+
+#[cfg(feature = "png")]
+impl From<png::DecodingError> for MapError {
+    fn from(error: png::DecodingError) -> Self {
+        MapErrorKind::Png(error).into()
+    }
+}
+
+#[derive(Debug)]
+pub enum MapErrorKind {
+    Io(io::Error),
+    Empty,
+    Ragged { line: usize, expected: usize, found: usize },
+    UnknownTile { line: usize, col: usize, tile: char },
+    Malformed(&'static str),
+    #[cfg(feature = "png")]
+    Png(png::DecodingError)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use super::super::PointState;
+
+    const BUILDING: &str = "
+#########
+#hh.....#
+#hh..#..#
+#....#.zz
+#########
+";
+
+    #[test]
+    fn test_ascii_map()
+    {
+        let map: GridMap<()> = Grid::from_ascii(BUILDING).unwrap();
+        let grid: &Grid<()> = map.get_grid();
+
+        assert_eq!(grid.get_dimensions(), (5, 9));
+        assert_eq!(grid.state(&PointIndex::new(0,0)), &PointState::Blocked);
+        assert_eq!(grid.state(&PointIndex::new(2,5)), &PointState::Blocked);
+        assert!(grid.state(&PointIndex::new(1,3)).is_empty());
+
+        assert_eq!(map.zone('h').map(|zone| zone.len()), Some(4));
+        assert_eq!(map.zone('z'), Some(&[PointIndex::new(3,7), PointIndex::new(3,8)][..]));
+        assert!(map.zone('x').is_none());
+        assert!(grid.state(&PointIndex::new(3,8)).is_empty());
+    }
+
+    #[test]
+    fn test_ascii_map_errors()
+    {
+        let ragged: _ = Grid::<()>::from_ascii("###\n##\n").unwrap_err();
+        match ragged.kind() {
+            MapErrorKind::Ragged { line: 2, expected: 3, found: 2 } => { },
+            other => panic!("unexpected error {:?}", other)
+        }
+
+        let unknown: _ = Grid::<()>::from_ascii("#.#\n#?#\n").unwrap_err();
+        assert_eq!(unknown.to_string(), "unknown tile '?' at line 2, column 2");
+
+        assert!(Grid::<()>::from_ascii("\n\n").is_err());
+        assert!(Grid::<()>::load_ascii("/nonexistent/map.txt").is_err());
+    }
+
+    #[test]
+    fn test_pgm_map()
+    {
+        let plain: &[u8] = b"P2\n# a river between two banks\n3 2\n15\n15 0 15\n15 7 15\n";
+        let map: GridMap<()> = Grid::from_pgm(plain, Paint::by_shade).unwrap();
+        let grid: &Grid<()> = map.get_grid();
+
+        assert_eq!(grid.get_dimensions(), (2, 3));
+        assert_eq!(grid.terrain(&PointIndex::new(0,0)), Terrain::Open);
+        assert_eq!(grid.terrain(&PointIndex::new(0,1)), Terrain::Impassable);
+        assert_eq!(grid.terrain(&PointIndex::new(1,1)), Terrain::Rough(1.0 + 136.0 / 32.0));
+
+        let mut binary: Vec<u8> = b"P5 2 2 255\n".to_vec();
+        binary.extend_from_slice(&[0, 64, 128, 255]);
+        let scent: _ = |(shade, _, _): (u8,u8,u8)| vec![
+            Paint::Field("scent".to_string(), FieldValue::Scalar(shade as f64 / 255.0)),
+            Paint::Zone(if shade == 0 { 's' } else { '.' })
+        ];
+        let map: GridMap<()> = Grid::from_pgm(&binary, scent).unwrap();
+
+        assert_eq!(map.get_grid().read_field("scent", &PointIndex::new(1,1)), Some(FieldValue::Scalar(1.0)));
+        assert_eq!(map.zone('s'), Some(&[PointIndex::new(0,0)][..]));
+
+        assert!(Grid::<()>::from_pgm(b"P5 2 2 255\n\x00", Paint::by_shade).is_err());
+        assert!(Grid::<()>::from_pgm(b"P6 1 1 255\n\x00\x00\x00", Paint::by_shade).is_err());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_png_map()
+    {
+        let mut bytes: Vec<u8> = Vec::new();
+        {
+            let mut encoder: png::Encoder<&mut Vec<u8>> = png::Encoder::new(&mut bytes, 2, 1);
+            encoder.set_color(png::ColorType::RGB);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.write_header()
+                .unwrap()
+                .write_image_data(&[255, 0, 0, 0, 0, 255])
+                .unwrap();
+        }
+        let palette: _ = |(r, _, b): (u8,u8,u8)| match (r, b) {
+            (255, _) => vec![Paint::Zone('z')],
+            (_, 255) => vec![Paint::Terrain(Terrain::Impassable)],
+            _ => Vec::new()
+        };
+        let map: GridMap<()> = Grid::from_png(&bytes[..], palette).unwrap();
+
+        assert_eq!(map.zone('z'), Some(&[PointIndex::new(0,0)][..]));
+        assert_eq!(map.get_grid().terrain(&PointIndex::new(0,1)), Terrain::Impassable);
+    }
+}
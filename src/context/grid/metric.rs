@@ -0,0 +1,271 @@
+use super::{Grid, PointIndex};
+
+use std::{fmt,cmp,hash};
+
+/// How the separation between two points is measured. Every metric is computed in integers
+/// from the per-axis deltas, which already honour the grid's boundary policy.
+#[derive(Debug,Default,Clone,Copy,PartialEq)]
+pub enum Metric {
+    Manhattan,
+    Chebyshev,
+    #[default]
+    Euclidean,
+    SquaredEuclidean
+}
+
+impl Metric {
+    pub fn measure(self, (dr,dc): (usize,usize)) -> Distance {
+        self.measure_axes(&[dr, dc])
+    }
+
+    /// Measures from per-axis deltas on a lattice of any dimension.
+    pub fn measure_axes(self, deltas: &[usize]) -> Distance {
+        let squares: usize = deltas.iter().map(|delta| delta * delta).sum();
+        match self {
+            Metric::Manhattan => Distance::exact(deltas.iter().sum()),
+            Metric::Chebyshev => Distance::exact(deltas.iter().copied().max().unwrap_or(0)),
+            Metric::Euclidean => Distance::squared(squares),
+            Metric::SquaredEuclidean => Distance::exact(squares)
+        }
+    }
+}
+
+/// A distance that compares exactly. Euclidean distances keep their square so that no 
+/// precision is lost before two of them are compared, and exact and squared distances 
+/// compare by the value they stand for.
+#[derive(Clone,Copy)]
+pub struct Distance {
+    raw: usize,
+    is_squared: bool
+}
+
+impl Distance {
+    pub fn exact(raw: usize) -> Self {
+        Self { raw, is_squared: false }
+    }
+
+    pub fn squared(raw: usize) -> Self {
+        Self { raw, is_squared: true }
+    }
+
+    /// Whether the distance is no greater than `radius`.
+    pub fn within(&self, radius: usize) -> bool {
+        if self.is_squared {
+            self.raw <= radius * radius
+        } else {
+            self.raw <= radius
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        if self.is_squared {
+            (self.raw as f64).sqrt()
+        } else {
+            self.raw as f64
+        }
+    }
+
+    /// The distance rounded down to a whole number of points.
+    pub fn as_usize(&self) -> usize {
+        if self.is_squared {
+            integer_sqrt(self.raw)
+        } else {
+            self.raw
+        }
+    }
+
+    // Both kinds squared, widened so that squaring an exact distance cannot overflow.
+    fn square(&self) -> u128 {
+        if self.is_squared {
+            self.raw as u128
+        } else {
+            self.raw as u128 * self.raw as u128
+        }
+    }
+}
+
+impl PartialEq for Distance {
+    fn eq(&self, other: &Self) -> bool {
+        self.square() == other.square()
+    }
+}
+
+impl Eq for Distance { }
+
+impl PartialOrd for Distance {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Distance {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.square().cmp(&other.square())
+    }
+}
+
+impl hash::Hash for Distance {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.square().hash(state)
+    }
+}
+
+impl fmt::Debug for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_f64())
+    }
+}
+
+fn integer_sqrt(value: usize) -> usize 
+{
+    let mut root: usize = (value as f64).sqrt() as usize;
+    while root * root > value {
+        root -= 1
+    }
+    while (root + 1) * (root + 1) <= value {
+        root += 1
+    }
+    root
+}
+
+impl<T> Grid<T> 
+{
+    /// Measures the grid's distances with the given metric.
+    pub fn with_metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn get_metric(&self) -> Metric {
+        self.metric
+    }
+
+    pub fn distance(&self, lhs: &PointIndex, rhs: &PointIndex) -> Distance {
+        self.metric.measure(self.axis_deltas(lhs, rhs))
+    }
+
+    /// Whether the straight line from `lhs` to `rhs` crosses no blocked point, going the 
+    /// shorter way around a wrapping grid. The end points themselves never block the view.
+    pub fn line_of_sight(&self, lhs: &PointIndex, rhs: &PointIndex) -> bool {
+        let dr: isize = self.boundary.offset(lhs.row, rhs.row, self.row);
+        let dc: isize = self.boundary.offset(lhs.col, rhs.col, self.col);
+        let steps: isize = isize::max(dr.abs(), dc.abs());
+
+        (1..steps).all(|step| {
+            // the offset `step` points along the line, rounded to the nearest point
+            let along: _ = |delta: isize| (2 * step * delta + steps * delta.signum()) / (2 * steps);
+            let row: Option<usize> = self.boundary.shift(lhs.row, along(dr), self.row);
+            let col: Option<usize> = self.boundary.shift(lhs.col, along(dc), self.col);
+            match (row, col) {
+                (Some(row), Some(col)) => !self.state(&PointIndex::new(row, col)).is_blocked(),
+                _ => false
+            }
+        })
+    }
+
+    // The per-axis separation of two indices under the grid's boundary policy.
+    pub(super) fn axis_deltas(&self, lhs: &PointIndex, rhs: &PointIndex) -> (usize,usize) {
+        (
+            self.boundary.delta(lhs.row, rhs.row, self.row),
+            self.boundary.delta(lhs.col, rhs.col, self.col)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use super::super::Boundary;
+
+    fn measure(grid: &Grid<()>, lhs: (usize,usize), rhs: (usize,usize)) -> Distance {
+        grid.distance(&PointIndex::new(lhs.0, lhs.1), &PointIndex::new(rhs.0, rhs.1))
+    }
+
+    #[test]
+    fn test_metrics_on_torus() 
+    {
+        let expectations: [(Metric, usize); 4] = [
+            (Metric::Manhattan, 5),
+            (Metric::Chebyshev, 3),
+            (Metric::Euclidean, 3),
+            (Metric::SquaredEuclidean, 13)
+        ];
+        for (metric, expected) in expectations.iter() {
+            let grid: Grid<()> = Grid::new(10,20).with_metric(*metric);
+            assert_eq!(measure(&grid, (1,1), (8,19)).as_usize(), *expected);
+        }
+    }
+
+    #[test]
+    fn test_metrics_on_closed_grid() 
+    {
+        let grid: Grid<()> = Grid::new(10,20)
+            .with_boundary(Boundary::Closed)
+            .with_metric(Metric::Manhattan);
+        assert_eq!(measure(&grid, (1,1), (8,19)).as_usize(), 25);
+    }
+
+    #[test]
+    fn test_euclidean_compares_exactly() 
+    {
+        let grid: Grid<()> = Grid::new(10,10);
+
+        let diagonal: Distance = measure(&grid, (0,0), (1,1));
+        let straight: Distance = measure(&grid, (0,0), (0,1));
+
+        assert!(straight < diagonal);
+        assert_eq!(diagonal.as_usize(), straight.as_usize());
+        assert!((diagonal.as_f64() - 2f64.sqrt()).abs() < 1e-12);
+        assert!(diagonal.within(2) && !diagonal.within(1));
+    }
+
+    #[test]
+    fn test_exact_and_squared_distances_compare_by_value() 
+    {
+        assert_eq!(Distance::exact(4), Distance::squared(16));
+        assert!(Distance::exact(2) > Distance::squared(3));
+        assert!(Distance::exact(2) < Distance::squared(5));
+        assert!(Distance::squared(1) < Distance::exact(2));
+        assert_eq!(Distance::exact(usize::MAX).cmp(&Distance::squared(usize::MAX)), cmp::Ordering::Greater);
+
+        let mut distances: Vec<Distance> = vec![Distance::exact(3), Distance::squared(5), Distance::exact(1), Distance::squared(10)];
+        distances.sort();
+        assert_eq!(distances.iter().map(|distance| distance.as_f64()).collect::<Vec<f64>>(), vec![1.0, 5f64.sqrt(), 3.0, 10f64.sqrt()]);
+    }
+
+    #[test]
+    fn test_line_of_sight() 
+    {
+        let walled: _ = |boundary: Boundary| -> Grid<()> {
+            let mut grid: Grid<()> = Grid::new(10,10).with_boundary(boundary);
+            for row in 0..9 {
+                grid.replace(&PointIndex::new(row, 5), super::super::PointState::Blocked);
+            }
+            grid
+        };
+        let sees: _ = |grid: &Grid<()>, lhs: (usize,usize), rhs: (usize,usize)| {
+            grid.line_of_sight(&PointIndex::new(lhs.0, lhs.1), &PointIndex::new(rhs.0, rhs.1))
+        };
+
+        let grid: Grid<()> = walled(Boundary::Toroidal);
+        assert!(!sees(&grid, (2,3), (2,7)));
+        assert!(!sees(&grid, (0,3), (6,8)));
+        assert!(sees(&grid, (9,3), (9,7)));
+        assert!(sees(&grid, (2,3), (8,0)));
+        assert!(sees(&grid, (2,5), (2,7)));
+
+        // the short way round the torus passes the wall by
+        assert!(sees(&grid, (2,1), (2,8)));
+        assert!(!sees(&walled(Boundary::Closed), (2,1), (2,8)));
+    }
+
+    #[test]
+    fn test_integer_sqrt() {
+        for value in 0..10_000 {
+            let root: usize = integer_sqrt(value);
+            assert!(root * root <= value && (root + 1) * (root + 1) > value);
+        }
+    }
+}
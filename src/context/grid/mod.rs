@@ -1,104 +1,393 @@
-use std::{rc,cell,ops,fmt};
+use std::{rc,cell,ops,fmt,collections};
 
-use crate::utils;
+use crate::{utils,context};
 
 use utils::Cell;
 
-use utils::sync::GreedyLock;
-
-mod connection;
-
-pub use connection::Connection;
+mod field;
+mod boundary;
+mod path;
+mod flow;
+mod occupancy;
+mod metric;
+mod storage;
+mod terrain;
+mod map;
+mod placement;
+
+pub use field::{Field, FieldValue, Dynamics};
+pub use boundary::{Boundary, Direction};
+pub use path::{Obstacles, Heuristic};
+pub use flow::{DistanceMap, FlowField};
+pub use occupancy::Occupancy;
+pub use metric::{Metric, Distance};
+pub use storage::{PointMap, PointStore, Layout};
+pub use terrain::Terrain;
+pub use map::{GridMap, Paint, MapError, MapErrorKind};
+pub use placement::{Placement, Spawner, PlacementError, PlacementErrorKind};
 
 #[derive(Debug)]
 pub struct Grid<T> {
     row: usize,
     col: usize,
-    points: Vec<Cell<Point<T>>>
+    boundary: Boundary,
+    metric: Metric,
+    layout: Layout,
+    points: PointStore<T>,
+    costs: PointMap<f64>,
+    occupancy: Occupancy,
+    fields: field::Fields,
+    flows: collections::HashMap<String, FlowField>,
+    epoch: u64
 }
 
 impl<T> Grid<T> 
 {
-    pub fn new(row: usize, col: usize) -> Self 
+    pub fn new(row: usize, col: usize) -> Self {
+        Self::with_capacity(row, col, Capacity::Bounded(1))
+    }
+
+    /// Builds a grid where every point starts out accepting up to `capacity` occupants.
+    pub fn with_capacity(row: usize, col: usize, capacity: Capacity) -> Self {
+        Self::with_layout(row, col, capacity, Layout::Dense)
+    }
+
+    /// Builds a grid that only stores the points holding something, for worlds far too large to allocate.
+    pub fn sparse(row: usize, col: usize) -> Self {
+        Self::with_layout(row, col, Capacity::Bounded(1), Layout::Sparse)
+    }
+
+    pub fn with_layout(row: usize, col: usize, capacity: Capacity, layout: Layout) -> Self 
     {
-        let mut points: Vec<Cell<Point<T>>> = Vec::new();
+        Grid {
+            row,
+            col,
+            boundary: Boundary::default(),
+            metric: Metric::default(),
+            layout,
+            points: PointStore::new(layout, row * col, capacity),
+            costs: PointMap::new(layout, row * col, 1.0),
+            occupancy: Occupancy::default(),
+            fields: Default::default(),
+            flows: collections::HashMap::new(),
+            epoch: 0
+        }
+    }
 
-        for row_idx in 0..row {
-            for col_idx in 0..col
-            {
-                let index: _ = PointIndex::new(row_idx,col_idx);
+    /// Sets the boundary policy that neighbours are computed under.
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
 
-                let point: Cell<Point<T>> = {
-                    let inner: _ = Point::new(index);
-                    rc::Rc::new(cell::RefCell::new(inner)) 
-                };
+    pub fn get_boundary(&self) -> Boundary {
+        self.boundary
+    }
 
-                points.push(point)
-            }
+    pub fn get_dimensions(&self) -> (usize,usize) {
+        (self.row, self.col)
+    }
+
+    pub fn get_layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// How many point states have been replaced so far; flow fields use it to notice changes.
+    pub fn get_epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    // Per-point storage laid out the same way as the grid's own.
+    fn point_map<V: Clone + PartialEq>(&self, default: V) -> PointMap<V> {
+        PointMap::new(self.layout, self.row * self.col, default)
+    }
+
+    pub fn into_cell(self) -> Cell<Self> {
+        rc::Rc::new(cell::RefCell::new(self))
+    }
+
+    /// The Euclidean distance between two points with both axes wrapped, rounded down.
+    pub fn toroidal_distance_between(&self, lhs: &PointView<T>, rhs: &PointView<T>) -> usize {
+        let dr: usize = Boundary::Toroidal.delta(lhs.index.row, rhs.index.row, self.row);
+        let dc: usize = Boundary::Toroidal.delta(lhs.index.col, rhs.index.col, self.col);
+
+        Metric::Euclidean
+            .measure((dr,dc))
+            .as_usize()
+    }
+
+    /// A read-only view of the point at `idx`.
+    pub fn point(&self, idx: &PointIndex) -> PointView<T> {
+        PointView {
+            index: idx.clone(),
+            state: self.state(idx),
+            capacity: self.points.capacity(self.flat_index(idx))
         }
+    }
 
-        let grid: Self = Grid {row,col,points};
+    /// Views of every point along `row`, made as they are iterated rather than kept.
+    pub fn row(&self, row: usize) -> impl Iterator<Item=PointView<'_,T>> + '_ {
+        assert!(row < self.row, "row {} lies outside a {}x{} grid", row, self.row, self.col);
+        (0..self.col).map(move |col| self.point(&PointIndex::new(row, col)))
+    }
 
-        for locked_point in grid.points
+    /// The neighbours of `idx` in north, east, south, west order, skipping any beyond a closed edge.
+    pub fn get_connections(&self, idx: &PointIndex) -> Vec<PointIndex> {
+        self.boundary
+            .neighbours(idx, self.get_dimensions())
             .iter()
-        {
-            let mut point: cell::RefMut<Point<T>> = locked_point.await_greedy_lock();
+            .filter_map(|neighbour| neighbour.clone())
+            .collect()
+    }
 
-            point.connections = Some(connection::Connection::new(&point.index,&grid))
+    pub fn state(&self, idx: &PointIndex) -> &PointState<T> {
+        self.points.get(self.flat_index(idx))
+    }
+
+    /// The index of the point at `row` and `col`, or None if it lies outside the grid.
+    pub fn index(&self, row: usize, col: usize) -> Option<PointIndex> {
+        if row < self.row && col < self.col {
+            Some(PointIndex::new(row, col))
+        } else {
+            None
         }
+    }
 
-        grid
+    fn flat_index(&self, idx: &PointIndex) -> usize {
+        // a column past the edge would alias a point on the next row
+        assert!(idx.row < self.row && idx.col < self.col, "{:?} lies outside a {}x{} grid", idx, self.row, self.col);
+        idx.row * self.col + idx.col
     }
 
-    pub fn get_dimensions(&self) -> (usize,usize) {
-        (self.row, self.col)
+    pub fn replace(&mut self, idx: &PointIndex, new_value: PointState<T>) -> PointState<T> 
+    {
+        let flat_idx: usize = self.flat_index(idx);
+        let old_value: PointState<T> = self.points.replace(flat_idx, new_value);
+        self.touch(idx, old_value.occupancy());
+        old_value
     }
 
-    pub fn into_cell(self) -> Cell<Self> {
-        rc::Rc::new(cell::RefCell::new(self))
+    // Keeps the occupancy index and the flow fields in step with a point whose state just changed.
+    fn touch(&mut self, idx: &PointIndex, old_occupancy: usize) 
+    {
+        match (old_occupancy, self.state(idx).occupancy()) {
+            (0, new) if new > 0 => self.occupancy.insert(idx),
+            (old, 0) if old > 0 => self.occupancy.remove(idx),
+            _ => { }
+        }
+
+        self.epoch += 1;
+        for flow in self.flows.values_mut() {
+            flow.touch(idx, self.epoch)
+        }
     }
 
-    pub fn toroidal_distance_between(&self, lhs: &Point<T>, rhs: &Point<T>) -> usize {
-        let delta: _ = |a1,a2,bound| -> f32 {
-            let abs_no_bound: _ = f32::abs(a2-a1);
-            let abs_bound: _ = f32::abs(a2 + bound - a1);
-            f32::min(abs_bound, abs_no_bound)
-        };
+    pub fn move_inner_into(&mut self, from: &PointIndex, to: &PointIndex) {
+        let inner: PointState<T> = self.replace(from, PointState::Empty);
+        self.replace(to, inner);
+    }
+
+    pub fn set_capacity(&mut self, idx: &PointIndex, capacity: Capacity) {
+        let flat_idx: usize = self.flat_index(idx);
+        self.points.set_capacity(flat_idx, capacity)
+    }
+
+    /// Adds an occupant to the point, handing it back if the point is already at capacity.
+    pub fn admit(&mut self, idx: &PointIndex, occupant: T) -> Result<(), T> 
+    {
+        let flat_idx: usize = self.flat_index(idx);
+        let old_occupancy: usize = self.state(idx).occupancy();
+
+        self.points.admit(flat_idx, occupant)?;
+        self.touch(idx, old_occupancy);
+        Ok(())
+    }
+
+    /// Removes and returns the first occupant at `idx` that satisfies `which`.
+    pub fn release_where<F>(&mut self, idx: &PointIndex, which: F) -> Option<T> 
+    where
+        F: Fn(&T) -> bool
+    {
+        let flat_idx: usize = self.flat_index(idx);
+        let old_occupancy: usize = self.state(idx).occupancy();
+
+        let released: Option<T> = self.points.release_where(flat_idx, which);
+        if released.is_some() {
+            self.touch(idx, old_occupancy)
+        }
+        released
+    }
+
+    /// Moves the first occupant of `from` satisfying `which` onto `to` if it has room.
+    pub fn transfer_where<F>(&mut self, from: &PointIndex, to: &PointIndex, which: F) -> bool 
+    where
+        F: Fn(&T) -> bool
+    {
+        let (from_idx, to_idx): (usize,usize) = (self.flat_index(from), self.flat_index(to));
+        let old_occupancy: (usize,usize) = (self.state(from).occupancy(), self.state(to).occupancy());
+
+        let transferred: bool = self.points.transfer_where(from_idx, to_idx, which);
+        if transferred {
+            self.touch(from, old_occupancy.0);
+            self.touch(to, old_occupancy.1)
+        }
+        transferred
+    }
+
+    /// Adds a named layer holding `initial` at every point, replacing any layer of the same name.
+    pub fn add_field<S: Into<String>>(&mut self, name: S, initial: FieldValue) -> Option<Field> {
+        let field: Field = Field::new(self.point_map(initial));
+        self.fields.insert(name.into(), field)
+    }
+
+    pub fn get_field(&self, name: &str) -> Option<&Field> {
+        self.fields.get(name)
+    }
 
-        let width: f32 = self.row as f32;
-        let x1: f32 = lhs.index.row as f32;
-        let x2: f32 = rhs.index.row as f32;
+    pub fn get_field_mut(&mut self, name: &str) -> Option<&mut Field> {
+        self.fields.get_mut(name)
+    }
 
-        let dx: f32 = delta(x1,x2,width);
+    pub fn read_field(&self, name: &str, idx: &PointIndex) -> Option<FieldValue> {
+        let flat_idx: usize = self.flat_index(idx);
+        self.fields
+            .get(name)
+            .map(|field| field.get(flat_idx))
+    }
+
+    pub fn write_field(&mut self, name: &str, idx: &PointIndex, value: FieldValue) -> Option<FieldValue> {
+        let flat_idx: usize = self.flat_index(idx);
+        self.fields
+            .get_mut(name)
+            .map(|field| field.set(flat_idx, value))
+    }
 
-        let height: f32 = self.col as f32;
-        let y1: f32 = lhs.index.col as f32;
-        let y2: f32 = rhs.index.col as f32;
+    /// Registers a hook that `update_fields` runs over every point of the named layer.
+    pub fn on_field_update<S,F>(&mut self, name: S, hook: F) 
+    where
+        S: Into<String>,
+        F: FnMut(&PointIndex, &mut FieldValue) + 'static
+    {
+        self.fields.push_hook(name.into(), Box::new(hook))
+    }
+
+    /// Applies built-in dynamics to the named layer on every `update_fields`, after the hooks.
+    ///
+    /// Panics on a diffusion or decay rate out of range, as `Dynamics::diffusion` and `Dynamics::decay` do.
+    pub fn add_dynamics<S: Into<String>>(&mut self, name: S, dynamics: Dynamics) {
+        self.fields.push_dynamics(name.into(), dynamics.checked())
+    }
 
-        let dy: f32 = delta(y1,y2,height);
+    /// Runs every registered field hook and dynamic once; call this once per tick.
+    pub fn update_fields(&mut self) {
+        let dimensions: (usize,usize) = self.get_dimensions();
+        let boundary: Boundary = self.boundary;
 
-        (dx*dx + dy*dy).sqrt() as usize
+        self.fields.update(dimensions, |idx| boundary.neighbours(idx, dimensions))
     }
 }
 
-impl<T> ops::Index<usize> for Grid<T> 
+impl<T> context::Space for Grid<T> 
 {
-    type Output = [Cell<Point<T>>];
-    
-    fn index(&self, idx: usize) -> &Self::Output 
+    type Index = PointIndex;
+    type Position = context::GridPosition<T>;
+    type Occupant = T;
+    type Distance = Distance;
+
+    fn neighbours_of(&self, idx: &PointIndex) -> Vec<PointIndex> {
+        self.get_connections(idx)
+    }
+
+    fn distance_between(&self, lhs: &PointIndex, rhs: &PointIndex) -> Distance {
+        self.distance(lhs, rhs)
+    }
+
+    fn is_vacant(&self, idx: &PointIndex) -> bool {
+        self.state(idx).is_empty()
+    }
+
+    fn has_room(&self, idx: &PointIndex) -> bool {
+        self.point(idx).has_room()
+    }
+
+    fn occupants_of(&self, idx: &PointIndex) -> Vec<T> 
+    where
+        T: Clone
     {
-        let range_start: usize = idx * self.col;
-        let range_end: usize = range_start + self.col;
-        let range: std::ops::Range<usize> = range_start..range_end;
+        self.state(idx)
+            .occupants()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
 
-        &self.points[range]
+    fn relocate<F>(&mut self, from: &PointIndex, to: &PointIndex, which: F) -> bool 
+    where
+        F: Fn(&T) -> bool
+    {
+        self.transfer_where(from, to, which)
+    }
+}
+
+impl<'a,T> ops::Index<&'a PointIndex> for Grid<T> 
+{
+    type Output = PointState<T>;
+    
+    fn index(&self, idx: &'a PointIndex) -> &Self::Output {
+        self.state(idx)
     }
 }
 
+/// A borrowed view of a single point; changes go through the owning `Grid`.
+pub struct PointView<'a,T> {
+    index: PointIndex,
+    state: &'a PointState<T>,
+    capacity: Capacity
+}
+
+impl<'a,T> fmt::Debug for PointView<'a,T> 
+where
+    T: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} PointView {:?}", self.state, self.index)
+    }
+}
+
+impl<'a,T:PartialEq> PartialEq for PointView<'a,T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.state == other.state
+    }
+}
+
+impl<'a,T> PointView<'a,T> 
+{
+    pub fn state(&self) -> &'a PointState<T> {
+        self.state
+    }
+
+    pub fn get_idx(&self) -> PointIndex {
+        self.index.clone()
+    }
+
+    pub fn capacity(&self) -> Capacity {
+        self.capacity
+    }
+
+    pub fn has_room(&self) -> bool {
+        self.state.has_room(self.capacity)
+    }
+
+    pub fn occupants(&self) -> Vec<&'a T> {
+        self.state.occupants()
+    }
+}
+
+/// A handle on a single point of a shared grid, made on demand. Reads and changes go 
+/// through the grid, so it must not be borrowed while a handle changes its point.
 pub struct Point<T> {
     index: PointIndex,
-    state: PointState<T>,
-    connections: Option<connection::Connection<T>> 
+    grid: rc::Weak<cell::RefCell<Grid<T>>>
 }
 
 impl<T> fmt::Debug for Point<T> 
@@ -106,53 +395,92 @@ where
     T: fmt::Debug
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?} Point {:?}", self.state, self.index)
+        match self.grid.upgrade() {
+            Some(grid) => match grid.try_borrow() {
+                Ok(active_grid) => write!(f, "{:?} Point {:?}", active_grid.state(&self.index), self.index),
+                Err(_) => write!(f, "Point {:?}", self.index)
+            },
+            None => write!(f, "Point {:?}", self.index)
+        }
     }
 }
 
-impl<T:PartialEq> PartialEq for Point<T> {
+impl<T> PartialEq for Point<T> {
     fn eq(&self, other: &Self) -> bool {
-        (self.index == other.index && self.state == other.state) 
+        self.index == other.index && rc::Weak::ptr_eq(&self.grid, &other.grid)
     }
 }
 
 impl<T> Point<T> 
 {
-    fn new(index: PointIndex) -> Self {
-        Self {
-            index,
-            state: PointState::Empty,
-            connections: None
-        }
+    pub(super) fn new(index: PointIndex, grid: rc::Weak<cell::RefCell<Grid<T>>>) -> Self {
+        Self { index, grid }
+    }
+
+    pub fn into_cell(self) -> Cell<Self> {
+        rc::Rc::new(cell::RefCell::new(self))
     }
 
-    pub fn get_connections(&self) -> Result<[Cell<Point<T>>; 4], std::option::NoneError> {
-        Ok(self.connections
-            .as_ref()?
-            .get_connections())
-    }   
+    pub fn get_idx(&self) -> PointIndex {
+        self.index.clone()
+    }
 
-    pub fn state(&self) -> &PointState<T> {
-        &self.state
+    pub fn get_grid(&self) -> Cell<Grid<T>> {
+        self.grid
+            .upgrade()
+            .expect("expected the grid to outlive its points")
+    }
+
+    /// The handles on the neighbours in north, east, south, west order; errs beyond a closed edge.
+    pub fn get_connections(&self) -> Result<[Cell<Point<T>>; 4], std::option::NoneError> 
+    {
+        let grid: Cell<Grid<T>> = self.get_grid();
+        let active_grid: cell::Ref<Grid<T>> = grid.borrow();
+        let [north, east, south, west]: [Option<PointIndex>; 4] = active_grid.boundary
+            .neighbours(&self.index, active_grid.get_dimensions());
+
+        let tile: _ = |idx: Option<PointIndex>| -> Result<Cell<Point<T>>, std::option::NoneError> {
+            Ok(Point::new(idx.ok_or(std::option::NoneError)?, self.grid.clone()).into_cell())
+        };
+        Ok([tile(north)?, tile(east)?, tile(south)?, tile(west)?])
     }
 
     pub fn replace(&mut self, new_value: PointState<T>) -> PointState<T> {
-        std::mem::replace(&mut self.state, new_value)
+        self.get_grid()
+            .borrow_mut()
+            .replace(&self.index, new_value)
     }
 
     pub fn move_inner_into(&mut self, new_position: &mut Self) {
-        new_position.replace(self.replace(PointState::Empty));
+        self.get_grid()
+            .borrow_mut()
+            .move_inner_into(&self.index, &new_position.index)
     }
+}
 
-    pub fn get_idx(&self) -> PointIndex {
-        self.index.clone()
+/// How many occupants a single point may hold at once.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Capacity {
+    Bounded(usize),
+    Unbounded
+}
+
+impl Capacity {
+    pub fn admits(&self, occupancy: usize) -> bool {
+        match self {
+            Capacity::Bounded(limit) => occupancy <= *limit,
+            Capacity::Unbounded => true
+        }
     }
 }
 
 #[derive(PartialEq)]
 pub enum PointState<T> {
     Occupied(T),
+    Crowded(Vec<T>),
     Claimed,
+    /// Impassable terrain such as a wall or a river; never holds occupants.
+    Blocked,
     Empty
 }
 
@@ -163,7 +491,9 @@ where
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             PointState::Occupied(_) => write!(f, "Occupied"),
+            PointState::Crowded(inner) => write!(f, "Crowded({})", inner.len()),
             PointState::Claimed => write!(f, "Claimed"),
+            PointState::Blocked => write!(f, "Blocked"),
             PointState::Empty => write!(f, "Empty")  
         }
     }
@@ -177,22 +507,88 @@ impl<T> PointState<T> {
         }
     }
 
-    pub fn as_inner(&self) -> Option<&T> {
+    pub fn is_blocked(&self) -> bool {
+        match self {
+            PointState::Blocked => true,
+            _ => false
+        }
+    }
+
+    pub fn occupants(&self) -> Vec<&T> {
+        match self {
+            PointState::Occupied(inner) => vec![inner],
+            PointState::Crowded(inner) => inner.iter().collect(),
+            _ => Vec::new()
+        }
+    }
+
+    pub fn occupancy(&self) -> usize {
         match self {
-            PointState::Occupied(inner) => Some(inner),
-            _ => None
+            PointState::Occupied(_) => 1,
+            PointState::Crowded(inner) => inner.len(),
+            _ => 0
+        }
+    }
+
+    /// Whether one more occupant fits under `capacity`; a claimed or blocked point never has room.
+    pub fn has_room(&self, capacity: Capacity) -> bool {
+        match self {
+            PointState::Claimed | PointState::Blocked => false,
+            _ => capacity.admits(self.occupancy() + 1)
+        }
+    }
+
+    /// The state with `occupant` added alongside whoever is already here.
+    pub fn admitting(self, occupant: T) -> Self {
+        match self {
+            PointState::Occupied(inner) => PointState::Crowded(vec![inner, occupant]),
+            PointState::Crowded(mut inner) => {
+                inner.push(occupant);
+                PointState::Crowded(inner)
+            },
+            _ => PointState::Occupied(occupant)
+        }
+    }
+
+    /// The state without its first occupant satisfying `which`, along with that occupant.
+    pub fn releasing<F>(self, which: F) -> (Self, Option<T>) 
+    where
+        F: Fn(&T) -> bool
+    {
+        match self {
+            PointState::Occupied(inner) => {
+                if which(&inner) {
+                    (PointState::Empty, Some(inner))
+                } else {
+                    (PointState::Occupied(inner), None)
+                }
+            },
+            PointState::Crowded(mut inner) => {
+                let released: Option<T> = inner.iter()
+                    .position(|occupant| which(occupant))
+                    .map(|position| inner.remove(position));
+
+                let remaining: PointState<T> = if inner.len() == 1 {
+                    PointState::Occupied(inner.remove(0))
+                } else {
+                    PointState::Crowded(inner)
+                };
+                (remaining, released)
+            },
+            other => (other, None)
         }
     }
 }
 
-#[derive(PartialEq,Clone)]
+#[derive(PartialEq,Eq,Hash,Clone)]
 pub struct PointIndex {
     row: usize,
     col: usize,
 }
 
 impl PointIndex {
-    fn new(row: usize, col: usize) -> Self {
+    // unchecked; indices from outside the crate come from `Grid::index`
+    pub(crate) fn new(row: usize, col: usize) -> Self {
         Self {row,col}
     }
 
@@ -217,33 +613,67 @@ mod tests
     fn test_grid_index() 
     {
         let grid: Grid<()> = Grid::new(5,5);
+        let idx: PointIndex = PointIndex::new(2,2);
+
+        let source_point: PointView<()> = grid.point(&idx);
 
-        let source_point: _ = grid[2][2].borrow();
-        let expected_point: Point<()> = Point::new(PointIndex::new(2,2));
+        assert_eq!(source_point.get_idx(), idx);
+        assert_eq!(source_point.capacity(), Capacity::Bounded(1));
+        assert_eq!(&grid[&idx], &PointState::Empty);
 
-        assert_eq!(source_point.index, expected_point.index)
+        let row: Vec<PointIndex> = grid.row(2).map(|point| point.get_idx()).collect();
+        assert_eq!(row.len(), 5);
+        assert_eq!(row[2], idx);
+
+        assert_eq!(grid.index(2,2), Some(idx));
+        assert_eq!(grid.index(1,5), None);
+        assert_eq!(grid.index(5,0), None);
     }
 
     #[test]
-    fn test_get_connections()
+    #[should_panic(expected = "outside a 5x5 grid")]
+    fn test_out_of_bounds_index_does_not_alias() 
     {
         let grid: Grid<()> = Grid::new(5,5);
-        let source_point: _ = grid[2][2].borrow();
+        grid.state(&PointIndex::new(1,5));
+    }
 
-        let test_connections: _ = source_point
-            .get_connections()
-            .unwrap();
+    #[test]
+    fn test_get_connections()
+    {
+        let grid: Grid<()> = Grid::new(5,5);
 
-        let expected_connections: _ = [
-            grid[1][2].clone(), 
-            grid[2][3].clone(), 
-            grid[3][2].clone(), 
-            grid[2][1].clone()
+        let test_connections: _ = grid.get_connections(&PointIndex::new(2,2));
+        let expected_connections: _ = vec![
+            PointIndex::new(1,2), 
+            PointIndex::new(2,3), 
+            PointIndex::new(3,2), 
+            PointIndex::new(2,1)
         ];
 
         assert_eq!(test_connections, expected_connections);
     }
 
+    macro_rules! test_edge_connections {
+        ($boundary:expr,$src_row:literal,$src_col:literal,[$(($exp_row:literal,$exp_col:literal)),*]) => {
+            let grid: Grid<()> = Grid::new(5,5).with_boundary($boundary);
+            let test: _ = grid.get_connections(&PointIndex::new($src_row,$src_col));
+
+            assert_eq!(test, vec![$(PointIndex::new($exp_row,$exp_col)),*])
+        }
+    }
+
+    #[test]
+    fn test_toroidal_edge_connections() {
+        test_edge_connections!(Boundary::Toroidal, 0,4, [(4,4), (0,0), (1,4), (0,3)]);
+    }
+
+    #[test]
+    fn test_closed_edge_connections() {
+        test_edge_connections!(Boundary::Closed, 0,0, [(0,1), (1,0)]);
+        test_edge_connections!(Boundary::Closed, 4,2, [(3,2), (4,3), (4,1)]);
+    }
+
     #[ignore] #[test]
     fn test_pointstate() {
         unimplemented!()
@@ -252,19 +682,188 @@ mod tests
     #[test]
     fn test_move_into() 
     {
-        let grid: Grid<()> = Grid::new(5,5);
+        let mut grid: Grid<()> = Grid::new(5,5);
+        let (from, to): _ = (PointIndex::new(2,2), PointIndex::new(3,3));
 
-        grid[2][2].borrow_mut().replace(PointState::Occupied(()));
+        grid.replace(&from, PointState::Occupied(()));
         
-        match grid[2][2].borrow().state() {
+        match grid.state(&from) {
             PointState::Occupied(_) => { },
             _ => panic!("Point was not occupied!")
         }
 
-        grid[2][2].borrow_mut().move_inner_into(&mut grid[3][3].borrow_mut());
+        grid.move_inner_into(&from, &to);
 
-        println!("{:?}", grid[3][3]);
-        println!("{:?}", grid[2][2]);
+        assert!(grid.state(&from).is_empty());
+        assert_eq!(grid.state(&to), &PointState::Occupied(()));
+    }
+
+    #[test]
+    fn test_point_handles() 
+    {
+        let grid: Cell<Grid<()>> = Grid::new(5,5).with_boundary(Boundary::Closed).into_cell();
+        let handle: _ = |row: usize, col: usize| Point::new(PointIndex::new(row,col), rc::Rc::downgrade(&grid)).into_cell();
+        let (from, to): _ = (handle(2,2), handle(3,3));
+
+        from.borrow_mut().replace(PointState::Occupied(()));
+        from.borrow_mut().move_inner_into(&mut to.borrow_mut());
+        assert!(grid.borrow().state(&PointIndex::new(2,2)).is_empty());
+        assert_eq!(grid.borrow().state(&PointIndex::new(3,3)), &PointState::Occupied(()));
+
+        let connections: _ = from.borrow().get_connections().unwrap();
+        let expected_connections: _ = [handle(1,2), handle(2,3), handle(3,2), handle(2,1)];
+        assert_eq!(connections, expected_connections);
+
+        let corner: Cell<Point<()>> = handle(0,0);
+        assert!(corner.borrow().get_connections().is_err());
+    }
+
+    #[test]
+    fn test_crowded_point()
+    {
+        let mut grid: Grid<usize> = Grid::with_capacity(5,5,Capacity::Bounded(3));
+        let idx: PointIndex = PointIndex::new(2,2);
+
+        for occupant in 0..3 {
+            assert!(grid.admit(&idx, occupant).is_ok());
+        }
+        assert_eq!(grid.admit(&idx, 3), Err(3));
+        assert_eq!(grid.point(&idx).occupants(), vec![&0, &1, &2]);
+
+        assert_eq!(grid.release_where(&idx, |occupant| *occupant == 1), Some(1));
+        assert_eq!(grid.release_where(&idx, |occupant| *occupant == 1), None);
+        assert_eq!(grid.release_where(&idx, |occupant| *occupant == 0), Some(0));
+        assert_eq!(grid.state(&idx), &PointState::Occupied(2));
+    }
+
+    #[test]
+    fn test_transfer_where()
+    {
+        let mut grid: Grid<usize> = Grid::new(5,5);
+        let (from, to): _ = (PointIndex::new(2,2), PointIndex::new(3,3));
+        grid.set_capacity(&to, Capacity::Unbounded);
+
+        for occupant in 0..4 {
+            grid.replace(&from, PointState::Occupied(occupant));
+            assert!(grid.transfer_where(&from, &to, |_| true));
+        }
+        assert_eq!(grid.point(&to).occupants().len(), 4);
+
+        grid.set_capacity(&to, Capacity::Bounded(4));
+        grid.replace(&from, PointState::Occupied(4));
+        assert!(!grid.transfer_where(&from, &to, |_| true));
+        assert!(!grid.state(&from).is_empty());
+    }
+
+    #[test]
+    fn test_slots_are_recycled()
+    {
+        let mut grid: Grid<usize> = Grid::new(50,50);
+
+        for round in 0..10 {
+            for col in 0..50 {
+                grid.replace(&PointIndex::new(round, col), PointState::Occupied(col));
+                grid.replace(&PointIndex::new(round + 1, col), PointState::Claimed);
+            }
+            for col in 0..50 {
+                assert_eq!(grid.replace(&PointIndex::new(round, col), PointState::Empty), PointState::Occupied(col));
+            }
+        }
+
+        assert_eq!(grid.points.stored(), 50);
+        assert_eq!(grid.state(&PointIndex::new(10,7)), &PointState::Claimed);
+        assert_eq!(grid.occupied_points(), 0);
+    }
+
+    #[test]
+    fn test_sparse_grid_matches_dense()
+    {
+        let mut dense: Grid<usize> = Grid::new(8,8);
+        let mut sparse: Grid<usize> = Grid::with_layout(8,8,Capacity::Bounded(1),Layout::Sparse);
+
+        for grid in [&mut dense, &mut sparse].iter_mut() {
+            grid.add_field("scent", FieldValue::Scalar(0.0));
+            grid.add_dynamics("scent", Dynamics::Diffusion(0.2));
+            grid.add_dynamics("scent", Dynamics::Decay(0.1));
+            grid.write_field("scent", &PointIndex::new(0,0), FieldValue::Scalar(10.0));
+            for (id, (row, col)) in [(1,1), (7,7), (3,4)].iter().enumerate() {
+                grid.admit(&PointIndex::new(*row, *col), id).unwrap();
+            }
+            for _ in 0..5 {
+                grid.update_fields();
+            }
+        }
+
+        for flat_idx in 0..64 {
+            let idx: PointIndex = PointIndex::new(flat_idx / 8, flat_idx % 8);
+            assert_eq!(dense.state(&idx), sparse.state(&idx));
+            assert_eq!(dense.read_field("scent", &idx), sparse.read_field("scent", &idx));
+            let nearby: Vec<Vec<usize>> = [&dense, &sparse].iter()
+                .map(|grid| {
+                    let mut found: Vec<usize> = grid.within_radius(&idx, 2).into_iter().map(|(_, id)| id).collect();
+                    found.sort();
+                    found
+                }).collect();
+            assert_eq!(nearby[0], nearby[1]);
+        }
+
+        let obstacles: Obstacles = Obstacles { occupied: true, claimed: true };
+        let (from, to): _ = (PointIndex::new(0,0), PointIndex::new(5,6));
+        assert_eq!(dense.bfs(&from, &to, obstacles), sparse.bfs(&from, &to, obstacles));
+        assert_eq!(
+            dense.distance_map(vec![to.clone()], obstacles).get(&from), 
+            sparse.distance_map(vec![to.clone()], obstacles).get(&from)
+        );
+    }
+
+    #[test]
+    fn test_continental_sparse_grid()
+    {
+        let mut grid: Grid<usize> = Grid::sparse(100_000,100_000);
+        let (corner, far_corner): _ = (PointIndex::new(0,0), PointIndex::new(99_999,99_999));
+
+        grid.admit(&corner, 0).unwrap();
+        grid.admit(&far_corner, 1).unwrap();
+        assert_eq!(grid.within_radius(&corner, 2).len(), 2);
+        assert_eq!(grid.get_connections(&corner)[0], PointIndex::new(99_999,0));
+
+        grid.add_field("scent", FieldValue::Scalar(0.0));
+        grid.add_dynamics("scent", Dynamics::Diffusion(0.25));
+        grid.write_field("scent", &far_corner, FieldValue::Scalar(4.0));
+        grid.update_fields();
+
+        assert_eq!(grid.get_field("scent").map(|field| field.iter().count()), Some(4));
+        assert_eq!(grid.read_field("scent", &PointIndex::new(0,99_999)), Some(FieldValue::Scalar(1.0)));
+
+        let obstacles: Obstacles = Obstacles { occupied: true, claimed: true };
+        let path: _ = grid.astar(&corner, &PointIndex::new(0,99_995), None, Heuristic::Toroidal, obstacles).unwrap();
+        assert_eq!(path.len(), 6);
+    }
+
+    #[test]
+    fn test_field_layers()
+    {
+        let mut grid: Grid<()> = Grid::new(5,5);
+        let idx: PointIndex = PointIndex::new(1,3);
+
+        assert_eq!(grid.read_field("scent", &idx), None);
+
+        grid.add_field("scent", FieldValue::Scalar(0.0));
+        grid.add_field("wind", FieldValue::Vector(1.0, 0.0));
+
+        assert_eq!(grid.write_field("scent", &idx, FieldValue::Scalar(4.0)), Some(FieldValue::Scalar(0.0)));
+        assert_eq!(grid.read_field("scent", &idx), Some(FieldValue::Scalar(4.0)));
+        assert_eq!(grid.read_field("wind", &idx).and_then(|value| value.as_vector()), Some((1.0, 0.0)));
+
+        grid.on_field_update("scent", |_, value| {
+            if let FieldValue::Scalar(inner) = value {
+                *inner /= 2.0
+            }
+        });
+        grid.update_fields();
+
+        assert_eq!(grid.read_field("scent", &idx), Some(FieldValue::Scalar(2.0)));
+        assert_eq!(grid.read_field("scent", &PointIndex::new(0,0)), Some(FieldValue::Scalar(0.0)));
     }
 
     #[test]
@@ -272,8 +871,68 @@ mod tests
     {
         let grid: Grid<()> = Grid::new(5,5);
 
-        let test: _ = grid.toroidal_distance_between(&grid[4][4].borrow(), &grid[0][0].borrow());
+        let test: _ = grid.toroidal_distance_between(&grid.point(&PointIndex::new(4,4)), &grid.point(&PointIndex::new(0,0)));
 
         assert_eq!(test, 1);
     }
+
+    // The squared distance on a torus by trying every wrapped image of `rhs` around `lhs`.
+    fn brute_force_squared_distance((row,col): (usize,usize), lhs: (usize,usize), rhs: (usize,usize)) -> usize 
+    {
+        let mut best: usize = std::usize::MAX;
+        for row_shift in -1..=1isize {
+            for col_shift in -1..=1isize {
+                let dr: isize = rhs.0 as isize + row_shift * row as isize - lhs.0 as isize;
+                let dc: isize = rhs.1 as isize + col_shift * col as isize - lhs.1 as isize;
+                best = usize::min(best, (dr*dr + dc*dc) as usize);
+            }
+        }
+        best
+    }
+
+    fn assert_matches_brute_force(grid: &Grid<()>, lhs: (usize,usize), rhs: (usize,usize)) 
+    {
+        let expected: usize = brute_force_squared_distance(grid.get_dimensions(), lhs, rhs);
+
+        let (lhs, rhs): _ = (PointIndex::new(lhs.0, lhs.1), PointIndex::new(rhs.0, rhs.1));
+
+        let test: usize = grid.toroidal_distance_between(&grid.point(&lhs), &grid.point(&rhs));
+        assert!(test * test <= expected && (test + 1) * (test + 1) > expected, "{:?} to {:?}", lhs, rhs);
+
+        let squared: Distance = Metric::SquaredEuclidean.measure(grid.axis_deltas(&lhs, &rhs));
+        assert_eq!(squared, Distance::exact(expected));
+    }
+
+    #[test]
+    fn test_toroidal_distance_exhaustively_on_small_grids()
+    {
+        for row in 1..=9 {
+            for col in 1..=9 {
+                let grid: Grid<()> = Grid::new(row,col);
+                for lhs in 0..row*col {
+                    for rhs in 0..row*col {
+                        assert_matches_brute_force(&grid, (lhs / col, lhs % col), (rhs / col, rhs % col));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_toroidal_distance_on_random_rectangular_grids()
+    {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng: rand::rngs::StdRng = rand::rngs::StdRng::seed_from_u64(34);
+        let sizes: [(usize,usize); 5] = [(200,1000), (1000,200), (3,500), (499,2), (64,63)];
+
+        for (row,col) in sizes.iter() {
+            let grid: Grid<()> = Grid::new(*row,*col);
+            for _ in 0..2000 {
+                let lhs: (usize,usize) = (rng.gen_range(0, row), rng.gen_range(0, col));
+                let rhs: (usize,usize) = (rng.gen_range(0, row), rng.gen_range(0, col));
+                assert_matches_brute_force(&grid, lhs, rhs);
+            }
+        }
+    }
 }   
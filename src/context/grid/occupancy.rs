@@ -0,0 +1,299 @@
+use super::{Grid, PointIndex, Boundary, Distance};
+
+use std::collections;
+
+const BUCKET_SIZE: usize = 16;
+
+/// Tracks which points hold occupants, bucketed into square blocks so that spatial 
+/// queries only visit the blocks they overlap.
+#[derive(Debug,Default)]
+pub struct Occupancy {
+    buckets: collections::HashMap<(usize,usize), collections::HashSet<PointIndex>>
+}
+
+impl Occupancy {
+    fn bucket_of(idx: &PointIndex) -> (usize,usize) {
+        (idx.row / BUCKET_SIZE, idx.col / BUCKET_SIZE)
+    }
+
+    pub(super) fn insert(&mut self, idx: &PointIndex) {
+        self.buckets
+            .entry(Self::bucket_of(idx))
+            .or_insert_with(collections::HashSet::new)
+            .insert(idx.clone());
+    }
+
+    pub(super) fn remove(&mut self, idx: &PointIndex) {
+        let bucket: (usize,usize) = Self::bucket_of(idx);
+        if let Some(points) = self.buckets.get_mut(&bucket) {
+            points.remove(idx);
+            if points.is_empty() {
+                self.buckets.remove(&bucket);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets
+            .values()
+            .map(|points| points.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    // Every occupied point within the inclusive row and column ranges.
+    fn in_block(&self, rows: (usize,usize), cols: (usize,usize)) -> Vec<PointIndex> 
+    {
+        let mut found: Vec<PointIndex> = Vec::new();
+
+        for bucket_row in rows.0 / BUCKET_SIZE ..= rows.1 / BUCKET_SIZE {
+            for bucket_col in cols.0 / BUCKET_SIZE ..= cols.1 / BUCKET_SIZE {
+                if let Some(points) = self.buckets.get(&(bucket_row, bucket_col)) {
+                    found.extend(points
+                        .iter()
+                        .filter(|idx| rows.0 <= idx.row && idx.row <= rows.1 && cols.0 <= idx.col && idx.col <= cols.1)
+                        .cloned()
+                    )
+                }
+            }
+        }
+        found
+    }
+}
+
+// The inclusive ranges along one axis within `reach` of `centre`, split in two where they wrap.
+fn window(centre: usize, reach: usize, bound: usize, boundary: Boundary) -> Vec<(usize,usize)> 
+{
+    if 2 * reach + 1 >= bound {
+        return vec![(0, bound - 1)]
+    }
+    match boundary {
+        Boundary::Closed => vec![(centre.saturating_sub(reach), usize::min(centre + reach, bound - 1))],
+        Boundary::Toroidal => {
+            if centre < reach {
+                vec![(0, centre + reach), (bound + centre - reach, bound - 1)]
+            } else if centre + reach >= bound {
+                vec![(centre - reach, bound - 1), (0, centre + reach - bound)]
+            } else {
+                vec![(centre - reach, centre + reach)]
+            }
+        }
+    }
+}
+
+// The inclusive range from `start` to `end` along one axis, split in two where it wraps.
+fn span(start: usize, end: usize, bound: usize, boundary: Boundary) -> Vec<(usize,usize)> 
+{
+    match boundary {
+        Boundary::Toroidal if start > end => vec![(start, bound - 1), (0, end)],
+        _ => vec![(start, end)]
+    }
+}
+
+impl<T> Grid<T> 
+{
+    // Occupied points within `reach` of `idx` along both axes.
+    fn occupied_near(&self, idx: &PointIndex, reach: usize) -> Vec<PointIndex> 
+    {
+        let occupancy: &Occupancy = &self.occupancy;
+        let rows: Vec<(usize,usize)> = window(idx.row, reach, self.row, self.boundary);
+        let cols: Vec<(usize,usize)> = window(idx.col, reach, self.col, self.boundary);
+
+        rows.iter()
+            .flat_map(|row_range| cols
+                .iter()
+                .map(move |col_range| (*row_range, *col_range))
+            )
+            .flat_map(|(row_range, col_range)| occupancy.in_block(row_range, col_range))
+            .collect()
+    }
+
+    fn occupants_at(&self, idx: &PointIndex) -> Vec<T> 
+    where
+        T: Clone
+    {
+        self.state(idx)
+            .occupants()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Every occupant whose point lies within `radius` of `idx` under the grid's metric.
+    pub fn within_radius(&self, idx: &PointIndex, radius: usize) -> Vec<(PointIndex, T)> 
+    where
+        T: Clone
+    {
+        self.occupied_near(idx, radius)
+            .into_iter()
+            .filter(|other| self.distance(idx, other).within(radius))
+            .flat_map(|other| self.occupants_at(&other)
+                .into_iter()
+                .map(move |occupant| (other.clone(), occupant))
+            )
+            .collect()
+    }
+
+    /// The `k` occupants closest to `idx` under the grid's metric that satisfy `filter`, nearest first.
+    pub fn nearest_k<F>(&self, idx: &PointIndex, k: usize, filter: F) -> Vec<(PointIndex, T)> 
+    where
+        T: Clone,
+        F: Fn(&T) -> bool
+    {
+        let full_reach: usize = usize::max(self.row, self.col);
+        let mut reach: usize = BUCKET_SIZE;
+
+        loop {
+            let mut found: Vec<(Distance, PointIndex, T)> = self.occupied_near(idx, reach)
+                .into_iter()
+                .flat_map(|other| {
+                    let distance: Distance = self.distance(idx, &other);
+                    self.occupants_at(&other)
+                        .into_iter()
+                        .map(move |occupant| (distance, other.clone(), occupant))
+                })
+                .filter(|(_, _, occupant)| filter(occupant))
+                .collect();
+            found.sort_by_key(|(distance, _, _)| *distance);
+
+            // only hits within `reach` are certain to beat anything beyond the window
+            let settled: usize = found.iter()
+                .take_while(|(distance, _, _)| distance.within(reach))
+                .count();
+
+            if settled >= k || reach >= full_reach {
+                return found.into_iter()
+                    .take(k)
+                    .map(|(_, other, occupant)| (other, occupant))
+                    .collect()
+            }
+            reach *= 2;
+        }
+    }
+
+    /// How many occupants stand within the inclusive rectangle spanned by the two corners.
+    ///
+    /// On a torus, a corner past the other along an axis makes the rectangle wrap around that edge.
+    pub fn count_in_rect(&self, top_left: &PointIndex, bottom_right: &PointIndex) -> usize 
+    {
+        let rows: Vec<(usize,usize)> = span(top_left.row, bottom_right.row, self.row, self.boundary);
+        let cols: Vec<(usize,usize)> = span(top_left.col, bottom_right.col, self.col, self.boundary);
+
+        rows.iter()
+            .flat_map(|row_range| cols
+                .iter()
+                .map(move |col_range| (*row_range, *col_range))
+            )
+            .flat_map(|(row_range, col_range)| self.occupancy.in_block(row_range, col_range))
+            .map(|other| self.state(&other).occupancy())
+            .sum()
+    }
+
+    pub fn occupied_points(&self) -> usize {
+        self.occupancy.len()
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use super::super::{PointState, Capacity};
+
+    fn occupy(grid: &mut Grid<usize>, row: usize, col: usize, id: usize) {
+        grid.admit(&PointIndex::new(row,col), id).unwrap();
+    }
+
+    #[test]
+    fn test_index_follows_point_state() 
+    {
+        let mut grid: Grid<usize> = Grid::new(40,40);
+        occupy(&mut grid, 3, 3, 0);
+        occupy(&mut grid, 30, 30, 1);
+        assert_eq!(grid.occupied_points(), 2);
+
+        grid.move_inner_into(&PointIndex::new(3,3), &PointIndex::new(3,4));
+        grid.replace(&PointIndex::new(30,30), PointState::Empty);
+        assert_eq!(grid.occupied_points(), 1);
+        assert_eq!(grid.within_radius(&PointIndex::new(3,4), 0), vec![(PointIndex::new(3,4), 0)]);
+    }
+
+    #[test]
+    fn test_within_radius_wraps_on_torus() 
+    {
+        let mut grid: Grid<usize> = Grid::new(50,50);
+        occupy(&mut grid, 0, 0, 0);
+        occupy(&mut grid, 49, 49, 1);
+        occupy(&mut grid, 2, 2, 2);
+        occupy(&mut grid, 25, 25, 3);
+
+        let mut found: Vec<usize> = grid.within_radius(&PointIndex::new(0,0), 2)
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect();
+        found.sort();
+        assert_eq!(found, vec![0, 1]);
+
+        let mut closed: Grid<usize> = Grid::new(50,50).with_boundary(Boundary::Closed);
+        occupy(&mut closed, 0, 0, 0);
+        occupy(&mut closed, 49, 49, 1);
+        assert_eq!(closed.within_radius(&PointIndex::new(0,0), 2).len(), 1);
+    }
+
+    #[test]
+    fn test_nearest_k_with_filter() 
+    {
+        let mut grid: Grid<usize> = Grid::with_capacity(100,100,Capacity::Unbounded);
+        occupy(&mut grid, 10, 10, 0);
+        occupy(&mut grid, 10, 12, 1);
+        occupy(&mut grid, 10, 12, 2);
+        occupy(&mut grid, 60, 60, 3);
+        occupy(&mut grid, 90, 90, 4);
+
+        let nearest: Vec<usize> = grid.nearest_k(&PointIndex::new(10,11), 3, |_| true)
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect();
+        assert_eq!(nearest.len(), 3);
+        assert!(nearest.contains(&0) && nearest.contains(&1) && nearest.contains(&2));
+
+        let odd: _ = grid.nearest_k(&PointIndex::new(10,11), 2, |id| id % 2 == 1);
+        assert_eq!(odd, vec![(PointIndex::new(10,12), 1), (PointIndex::new(60,60), 3)]);
+    }
+
+    #[test]
+    fn test_count_in_rect() 
+    {
+        let mut grid: Grid<usize> = Grid::with_capacity(40,40,Capacity::Bounded(2));
+        occupy(&mut grid, 5, 5, 0);
+        occupy(&mut grid, 5, 5, 1);
+        occupy(&mut grid, 10, 20, 2);
+        occupy(&mut grid, 30, 30, 3);
+
+        assert_eq!(grid.count_in_rect(&PointIndex::new(0,0), &PointIndex::new(10,20)), 3);
+        assert_eq!(grid.count_in_rect(&PointIndex::new(6,6), &PointIndex::new(39,39)), 2);
+    }
+
+    #[test]
+    fn test_count_in_rect_wraps_across_the_seam() 
+    {
+        let mut grid: Grid<usize> = Grid::new(40,40);
+        occupy(&mut grid, 0, 0, 0);
+        occupy(&mut grid, 39, 39, 1);
+        occupy(&mut grid, 0, 39, 2);
+        occupy(&mut grid, 39, 0, 3);
+        occupy(&mut grid, 20, 20, 4);
+
+        assert_eq!(grid.count_in_rect(&PointIndex::new(38,38), &PointIndex::new(1,1)), 4);
+        assert_eq!(grid.count_in_rect(&PointIndex::new(0,38), &PointIndex::new(0,1)), 2);
+        assert_eq!(grid.count_in_rect(&PointIndex::new(10,38), &PointIndex::new(30,21)), 1);
+
+        let mut closed: Grid<usize> = Grid::new(40,40).with_boundary(Boundary::Closed);
+        occupy(&mut closed, 0, 0, 0);
+        assert_eq!(closed.count_in_rect(&PointIndex::new(38,38), &PointIndex::new(1,1)), 0);
+    }
+}
@@ -0,0 +1,306 @@
+use super::{Grid, PointIndex, PointState, PointMap, Boundary};
+
+use std::{collections,cmp};
+
+/// Which kinds of points a search may not pass through besides blocked ones. The goal is 
+/// always reachable unless it is blocked.
+#[derive(Debug,Clone,Copy,PartialEq,Default)]
+pub struct Obstacles {
+    pub occupied: bool,
+    pub claimed: bool
+}
+
+/// The estimate A* uses for the remaining cost to the goal.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Heuristic {
+    /// Step distance under the grid's own boundary.
+    Manhattan,
+    /// Step distance with wrapping on both axes, whatever the grid's boundary.
+    Toroidal
+}
+
+impl<T> Grid<T> 
+{
+    /// The shortest path by step count, including both `from` and `to`.
+    pub fn bfs(&self, from: &PointIndex, to: &PointIndex, obstacles: Obstacles) -> Option<Vec<PointIndex>> 
+    {
+        let mut came_from: PointMap<Option<usize>> = self.point_map(None);
+        let mut frontier: collections::VecDeque<PointIndex> = collections::VecDeque::new();
+
+        let start: usize = self.flat_index(from);
+        came_from.set(start, Some(start));
+        frontier.push_back(from.clone());
+
+        while let Some(current) = frontier.pop_front() 
+        {
+            if &current == to {
+                return Some(self.trace_path(&came_from, start, self.flat_index(to)))
+            }
+            for neighbour in self.passable_neighbours(&current, to, obstacles) {
+                let neighbour_idx: usize = self.flat_index(&neighbour);
+                if came_from.get(neighbour_idx).is_none() {
+                    came_from.set(neighbour_idx, Some(self.flat_index(&current)));
+                    frontier.push_back(neighbour)
+                }
+            }
+        }
+        None
+    }
+
+    /// The cheapest path where entering a point costs the scalar value of `cost_field` there,
+    /// scaled by the movement cost of its terrain.
+    pub fn dijkstra(&self, from: &PointIndex, to: &PointIndex, cost_field: &str, obstacles: Obstacles) -> Option<Vec<PointIndex>> {
+        self.cheapest_path(from, to, Some(cost_field), |_| 0.0, obstacles)
+    }
+
+    /// The cheapest path guided by `heuristic`, costing each step its terrain's movement cost 
+    /// unless `cost_field` is also given.
+    ///
+    /// The heuristic assumes every step costs at least one; cheaper steps may yield longer paths.
+    pub fn astar(
+        &self, 
+        from: &PointIndex, 
+        to: &PointIndex, 
+        cost_field: Option<&str>, 
+        heuristic: Heuristic, 
+        obstacles: Obstacles
+    ) -> Option<Vec<PointIndex>> {
+        let estimate: _ = |idx: &PointIndex| self.heuristic_between(idx, to, heuristic) as f64;
+        self.cheapest_path(from, to, cost_field, estimate, obstacles)
+    }
+
+    fn cheapest_path<H>(
+        &self, 
+        from: &PointIndex, 
+        to: &PointIndex, 
+        cost_field: Option<&str>, 
+        estimate: H, 
+        obstacles: Obstacles
+    ) -> Option<Vec<PointIndex>> 
+    where
+        H: Fn(&PointIndex) -> f64
+    {
+        let mut came_from: PointMap<Option<usize>> = self.point_map(None);
+        let mut best_cost: PointMap<f64> = self.point_map(f64::INFINITY);
+        let mut frontier: collections::BinaryHeap<Candidate> = collections::BinaryHeap::new();
+
+        let start: usize = self.flat_index(from);
+        came_from.set(start, Some(start));
+        best_cost.set(start, 0.0);
+        frontier.push(Candidate { priority: estimate(from), idx: from.clone() });
+
+        while let Some(Candidate { idx: current, .. }) = frontier.pop() 
+        {
+            if &current == to {
+                return Some(self.trace_path(&came_from, start, self.flat_index(to)))
+            }
+            let current_idx: usize = self.flat_index(&current);
+
+            for neighbour in self.passable_neighbours(&current, to, obstacles) 
+            {
+                let neighbour_idx: usize = self.flat_index(&neighbour);
+                let cost: f64 = best_cost.get(current_idx) + self.step_cost(cost_field, &neighbour);
+
+                if cost < best_cost.get(neighbour_idx) {
+                    best_cost.set(neighbour_idx, cost);
+                    came_from.set(neighbour_idx, Some(current_idx));
+                    frontier.push(Candidate { priority: cost + estimate(&neighbour), idx: neighbour })
+                }
+            }
+        }
+        None
+    }
+
+    // The field cost of entering `idx`, scaled by the movement cost of its terrain.
+    fn step_cost(&self, cost_field: Option<&str>, idx: &PointIndex) -> f64 {
+        let field_cost: f64 = cost_field
+            .and_then(|name| self.read_field(name, idx))
+            .and_then(|value| value.as_scalar())
+            .map(|cost| f64::max(cost, 0.0))
+            .unwrap_or(1.0);
+        field_cost * self.movement_cost(idx)
+    }
+
+    fn heuristic_between(&self, lhs: &PointIndex, rhs: &PointIndex, heuristic: Heuristic) -> usize {
+        let boundary: Boundary = match heuristic {
+            Heuristic::Manhattan => self.boundary,
+            Heuristic::Toroidal => Boundary::Toroidal
+        };
+        boundary.delta(lhs.row, rhs.row, self.row) + boundary.delta(lhs.col, rhs.col, self.col)
+    }
+
+    fn passable_neighbours(&self, idx: &PointIndex, goal: &PointIndex, obstacles: Obstacles) -> Vec<PointIndex> {
+        self.boundary
+            .neighbours(idx, self.get_dimensions())
+            .iter()
+            .filter_map(|neighbour| neighbour.clone())
+            .filter(|neighbour| {
+                !self.is_obstacle(neighbour, obstacles) 
+                    || (neighbour == goal && !self.state(neighbour).is_blocked())
+            })
+            .collect()
+    }
+
+    pub(super) fn is_obstacle(&self, idx: &PointIndex, obstacles: Obstacles) -> bool {
+        match self.state(idx) {
+            PointState::Occupied(_) | PointState::Crowded(_) => obstacles.occupied,
+            PointState::Claimed => obstacles.claimed,
+            PointState::Blocked => true,
+            PointState::Empty => false
+        }
+    }
+
+    fn trace_path(&self, came_from: &PointMap<Option<usize>>, start: usize, end: usize) -> Vec<PointIndex> 
+    {
+        let mut path: Vec<PointIndex> = Vec::new();
+        let mut current: usize = end;
+
+        while current != start {
+            path.push(PointIndex::new(current / self.col, current % self.col));
+            current = came_from.get(current).expect("expected a predecessor on the traced path");
+        }
+        path.push(PointIndex::new(start / self.col, start % self.col));
+        path.reverse();
+        path
+    }
+}
+
+// Orders the heap so that the cheapest candidate is popped first.
+struct Candidate {
+    priority: f64,
+    idx: PointIndex
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl Eq for Candidate { }
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        other.priority
+            .partial_cmp(&self.priority)
+            .unwrap_or(cmp::Ordering::Equal)
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use super::super::FieldValue;
+
+    // A wall down column 2 with a single gap at row 4.
+    fn walled_grid() -> Grid<()> 
+    {
+        let mut grid: Grid<()> = Grid::new(5,5).with_boundary(Boundary::Closed);
+        for row in 0..4 {
+            grid.replace(&PointIndex::new(row,2), PointState::Occupied(()));
+        }
+        grid
+    }
+
+    fn assert_connected(path: &[PointIndex]) {
+        for pair in path.windows(2) {
+            let (r1,c1): _ = pair[0].as_dimensions();
+            let (r2,c2): _ = pair[1].as_dimensions();
+            let step: usize = r1.abs_diff(r2) + c1.abs_diff(c2);
+            assert_eq!(step, 1)
+        }
+    }
+
+    #[test]
+    fn test_bfs_routes_around_occupied_points() 
+    {
+        let grid: _ = walled_grid();
+        let obstacles: Obstacles = Obstacles { occupied: true, claimed: true };
+
+        let path: _ = grid.bfs(&PointIndex::new(0,0), &PointIndex::new(0,4), obstacles).unwrap();
+
+        assert_eq!(path.first(), Some(&PointIndex::new(0,0)));
+        assert_eq!(path.last(), Some(&PointIndex::new(0,4)));
+        assert!(path.contains(&PointIndex::new(4,2)));
+        assert_eq!(path.len(), 13);
+        assert_connected(&path);
+
+        let direct: _ = grid.bfs(&PointIndex::new(0,0), &PointIndex::new(0,4), Obstacles::default()).unwrap();
+        assert_eq!(direct.len(), 5);
+    }
+
+    #[test]
+    fn test_goal_is_never_an_obstacle() 
+    {
+        let grid: _ = walled_grid();
+        let obstacles: Obstacles = Obstacles { occupied: true, claimed: true };
+
+        let path: _ = grid.bfs(&PointIndex::new(0,0), &PointIndex::new(0,2), obstacles).unwrap();
+        assert_eq!(path.len(), 3);
+    }
+
+    #[test]
+    fn test_unreachable_goal() 
+    {
+        let mut grid: Grid<()> = Grid::new(5,5).with_boundary(Boundary::Closed);
+        for row in 0..5 {
+            grid.replace(&PointIndex::new(row,2), PointState::Claimed);
+        }
+        let obstacles: Obstacles = Obstacles { occupied: false, claimed: true };
+
+        assert_eq!(grid.bfs(&PointIndex::new(0,0), &PointIndex::new(0,4), obstacles), None);
+        assert_eq!(grid.astar(&PointIndex::new(0,0), &PointIndex::new(0,4), None, Heuristic::Manhattan, obstacles), None);
+    }
+
+    #[test]
+    fn test_dijkstra_prefers_cheap_points() 
+    {
+        let mut grid: Grid<()> = Grid::new(3,3).with_boundary(Boundary::Closed);
+        grid.add_field("terrain", FieldValue::Scalar(1.0));
+        grid.write_field("terrain", &PointIndex::new(1,1), FieldValue::Scalar(10.0));
+
+        let path: _ = grid.dijkstra(&PointIndex::new(1,0), &PointIndex::new(1,2), "terrain", Obstacles::default()).unwrap();
+
+        assert_eq!(path.len(), 5);
+        assert!(!path.contains(&PointIndex::new(1,1)));
+        assert_connected(&path);
+    }
+
+    #[test]
+    fn test_astar_wraps_on_torus() 
+    {
+        let grid: Grid<()> = Grid::new(10,10);
+
+        let path: _ = grid.astar(&PointIndex::new(0,0), &PointIndex::new(9,9), None, Heuristic::Toroidal, Obstacles::default()).unwrap();
+        assert_eq!(path.len(), 3);
+        assert_connected_toroidal(&path, 10);
+
+        let bfs_path: _ = grid.bfs(&PointIndex::new(2,3), &PointIndex::new(7,8), Obstacles::default()).unwrap();
+        let astar_path: _ = grid.astar(&PointIndex::new(2,3), &PointIndex::new(7,8), None, Heuristic::Toroidal, Obstacles::default()).unwrap();
+        assert_eq!(bfs_path.len(), astar_path.len());
+
+        // the grid's own boundary keeps Manhattan admissible across the seam
+        let manhattan_path: _ = grid.astar(&PointIndex::new(0,0), &PointIndex::new(9,9), None, Heuristic::Manhattan, Obstacles::default()).unwrap();
+        assert_eq!(manhattan_path.len(), 3);
+    }
+
+    fn assert_connected_toroidal(path: &[PointIndex], bound: usize) {
+        for pair in path.windows(2) {
+            let (r1,c1): _ = pair[0].as_dimensions();
+            let (r2,c2): _ = pair[1].as_dimensions();
+            let delta: _ = |a: usize, b: usize| {
+                let direct: usize = a.abs_diff(b);
+                cmp::min(direct, bound - direct)
+            };
+            assert_eq!(delta(r1,r2) + delta(c1,c2), 1)
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use super::{Grid, PointIndex, PointState};
+
+use std::{collections, error, fmt, fs, io, path};
+
+use rand::Rng;
+
+/// Where newly seeded occupants are put down.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Placement {
+    /// Any free point of the grid, each drawn at most once.
+    Uniform,
+    /// Free points no more than `spread` steps from one of the centres, never reached through a wall.
+    Clustered { centres: Vec<PointIndex>, spread: usize },
+    /// Free points of the given spawn zones, drawn at random.
+    Zones(Vec<PointIndex>),
+    /// The listed points, in order.
+    Listed(Vec<PointIndex>)
+}
+
+impl Placement {
+    /// Restricts placement to the points of the zones labelled in `labels`.
+    pub fn in_zones(zones: &collections::HashMap<char, Vec<PointIndex>>, labels: &[char]) -> Result<Self, PlacementError>
+    {
+        let mut points: Vec<PointIndex> = Vec::new();
+        for label in labels.iter() {
+            let zone: &Vec<PointIndex> = zones
+                .get(label)
+                .ok_or(PlacementErrorKind::UnknownZone(*label))?;
+            points.extend(zone.iter().cloned())
+        }
+        Ok(Placement::Zones(points))
+    }
+
+    /// Reads a list of points, one `row col` or `row,col` pair per line; `#` starts a comment.
+    pub fn parse(text: &str) -> Result<Self, PlacementError>
+    {
+        let mut points: Vec<PointIndex> = Vec::new();
+        for (line, content) in text.lines().enumerate() {
+            let content: &str = content.split('#').next().unwrap_or("").trim();
+            if content.is_empty() {
+                continue
+            }
+            let coordinates: Vec<usize> = content
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|part| !part.is_empty())
+                .map(|part| part.parse())
+                .collect::<Result<_,_>>()
+                .map_err(|_| PlacementErrorKind::Malformed { line: line + 1 })?;
+            match coordinates.as_slice() {
+                [row, col] => points.push(PointIndex::new(*row, *col)),
+                _ => return Err(PlacementErrorKind::Malformed { line: line + 1 }.into())
+            }
+        }
+        Ok(Placement::Listed(points))
+    }
+
+    pub fn load<P: AsRef<path::Path>>(path: P) -> Result<Self, PlacementError> {
+        let text: String = fs::read_to_string(path)?;
+        Self::parse(&text)
+    }
+}
+
+#[derive(Debug)]
+enum Candidates {
+    Grid { row: usize, col: usize },
+    Points(Vec<PointIndex>)
+}
+
+impl Candidates {
+    fn len(&self) -> usize {
+        match self {
+            Candidates::Grid { row, col } => row * col,
+            Candidates::Points(points) => points.len()
+        }
+    }
+
+    fn get(&self, position: usize) -> PointIndex {
+        match self {
+            Candidates::Grid { col, .. } => PointIndex::new(position / col, position % col),
+            Candidates::Points(points) => points[position].clone()
+        }
+    }
+}
+
+/// Draws points for a `Placement`, claiming each one on the grid as it goes.
+///
+/// Random placements shuffle their candidates lazily, so every draw costs the same however
+/// large the grid is, and no candidate is drawn twice.
+#[derive(Debug)]
+pub struct Spawner {
+    candidates: Candidates,
+    shuffled: bool,
+    remaining: usize,
+    // the entries of the partially shuffled candidate order that differ from their position
+    swaps: collections::HashMap<usize, usize>
+}
+
+impl Spawner {
+    pub fn new<T>(grid: &Grid<T>, placement: Placement) -> Result<Self, PlacementError>
+    {
+        let (row, col): (usize, usize) = grid.get_dimensions();
+        let (candidates, shuffled): (Candidates, bool) = match placement {
+            Placement::Uniform => (Candidates::Grid { row, col }, true),
+            Placement::Clustered { centres, spread } => (Candidates::Points(Self::cluster(grid, centres, spread)?), true),
+            Placement::Zones(points) => (Candidates::Points(points), true),
+            Placement::Listed(points) => (Candidates::Points(points), false)
+        };
+
+        if let Candidates::Points(points) = &candidates {
+            if let Some(outside) = points.iter().find(|idx| idx.row >= row || idx.col >= col) {
+                return Err(PlacementErrorKind::OutOfBounds(outside.clone()).into())
+            }
+        }
+
+        Ok(Self {
+            remaining: candidates.len(),
+            candidates,
+            shuffled,
+            swaps: collections::HashMap::new()
+        })
+    }
+
+    // every point within `spread` steps of a centre, nearest rings first
+    fn cluster<T>(grid: &Grid<T>, centres: Vec<PointIndex>, spread: usize) -> Result<Vec<PointIndex>, PlacementError>
+    {
+        let (row, col): (usize, usize) = grid.get_dimensions();
+        if let Some(outside) = centres.iter().find(|idx| idx.row >= row || idx.col >= col) {
+            return Err(PlacementErrorKind::OutOfBounds(outside.clone()).into())
+        }
+
+        let mut seen: collections::HashSet<PointIndex> = centres.iter().cloned().collect();
+        let mut points: Vec<PointIndex> = centres.clone();
+        let mut ring: Vec<PointIndex> = centres;
+
+        for _ in 0..spread {
+            ring = ring.iter()
+                .filter(|idx| !grid.state(idx).is_blocked())
+                .flat_map(|idx| grid.get_connections(idx))
+                .filter(|neighbour| seen.insert(neighbour.clone()))
+                .collect();
+            points.extend(ring.iter().cloned());
+        }
+        Ok(points)
+    }
+
+    /// How many candidates have not been drawn yet.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Claims the next free candidate, skipping any that have been taken or walled off since.
+    pub fn place<T, R>(&mut self, grid: &mut Grid<T>, rng: &mut R) -> Result<PointIndex, PlacementError>
+    where
+        R: Rng + ?Sized
+    {
+        while let Some(position) = self.draw(rng) {
+            let idx: PointIndex = self.candidates.get(position);
+            if grid.state(&idx).is_empty() {
+                grid.replace(&idx, PointState::Claimed);
+                return Ok(idx)
+            }
+        }
+        Err(PlacementErrorKind::Exhausted.into())
+    }
+
+    // one step of a Fisher-Yates shuffle, recording only the swapped entries
+    fn draw<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<usize>
+    {
+        if self.remaining == 0 {
+            return None
+        }
+        let last: usize = self.remaining - 1;
+        self.remaining -= 1;
+
+        if !self.shuffled {
+            return Some(self.candidates.len() - last - 1)
+        }
+
+        let position: usize = rng.gen_range(0, last + 1);
+        let drawn: usize = self.swaps.remove(&position).unwrap_or(position);
+        if position != last {
+            let moved: usize = self.swaps.remove(&last).unwrap_or(last);
+            self.swaps.insert(position, moved);
+        }
+        Some(drawn)
+    }
+}
+
+#[derive(Debug)]
+pub struct PlacementError {
+    kind: PlacementErrorKind
+}
+
+impl PlacementError {
+    pub fn kind(&self) -> &PlacementErrorKind {
+        &self.kind
+    }
+}
+
+impl error::Error for PlacementError { }
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PlacementErrorKind::Exhausted => write!(f, "no free points are left to place on"),
+            PlacementErrorKind::UnknownZone(label) => write!(f, "the map has no zone {:?}", label),
+            PlacementErrorKind::OutOfBounds(idx) => write!(f, "{:?} lies outside the grid", idx),
+            PlacementErrorKind::Io(error) => write!(f, "could not read the placements: {}", error),
+            PlacementErrorKind::Malformed { line } => write!(f, "line {} is not a `row col` pair", line)
+        }
+    }
+}
+
+impl From<PlacementErrorKind> for PlacementError {
+    fn from(kind: PlacementErrorKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl From<io::Error> for PlacementError {
+    fn from(error: io::Error) -> Self {
+        PlacementErrorKind::Io(error).into()
+    }
+}
+
+#[derive(Debug)]
+pub enum PlacementErrorKind {
+    Exhausted,
+    UnknownZone(char),
+    OutOfBounds(PointIndex),
+    Io(io::Error),
+    Malformed { line: usize }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use rand::SeedableRng;
+
+    fn place_all(grid: &mut Grid<()>, placement: Placement) -> Vec<PointIndex>
+    {
+        let mut rng: rand::rngs::StdRng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut spawner: Spawner = Spawner::new(grid, placement).unwrap();
+        let mut placed: Vec<PointIndex> = Vec::new();
+
+        while let Ok(idx) = spawner.place(grid, &mut rng) {
+            placed.push(idx)
+        }
+        placed
+    }
+
+    #[test]
+    fn test_uniform_placement_fills_the_grid()
+    {
+        let mut grid: Grid<()> = Grid::new(7,9);
+        grid.replace(&PointIndex::new(3,3), PointState::Occupied(()));
+
+        let placed: Vec<PointIndex> = place_all(&mut grid, Placement::Uniform);
+        let distinct: collections::HashSet<_> = placed.iter().collect();
+        assert_eq!(placed.len(), 62);
+        assert_eq!(distinct.len(), 62);
+        assert!(!placed.contains(&PointIndex::new(3,3)));
+        assert_eq!(place_all(&mut Grid::new(7,9), Placement::Uniform), place_all(&mut Grid::new(7,9), Placement::Uniform));
+
+        let mut spawner: Spawner = Spawner::new(&grid, Placement::Uniform).unwrap();
+        let mut rng: rand::rngs::StdRng = rand::rngs::StdRng::seed_from_u64(7);
+        let error: PlacementError = spawner.place(&mut grid, &mut rng).unwrap_err();
+        assert!(matches!(error.kind(), PlacementErrorKind::Exhausted));
+    }
+
+    #[test]
+    fn test_uniform_placement_on_a_continent()
+    {
+        let mut grid: Grid<()> = Grid::sparse(100_000, 100_000);
+        let mut spawner: Spawner = Spawner::new(&grid, Placement::Uniform).unwrap();
+        let mut rng: rand::rngs::StdRng = rand::rngs::StdRng::seed_from_u64(7);
+
+        for _ in 0..1000 {
+            spawner.place(&mut grid, &mut rng).unwrap();
+        }
+        assert_eq!(spawner.remaining(), 100_000 * 100_000 - 1000);
+    }
+
+    #[test]
+    fn test_clustered_placement_stays_inside_walls()
+    {
+        let map: _ = Grid::<()>::from_ascii("
+.....#...
+.....#...
+.....#...
+").unwrap();
+        let mut grid: Grid<()> = map.into_grid().with_boundary(super::super::Boundary::Closed);
+
+        let centres: Vec<PointIndex> = vec![PointIndex::new(1,3)];
+        let placed: Vec<PointIndex> = place_all(&mut grid, Placement::Clustered { centres, spread: 4 });
+
+        assert_eq!(placed.len(), 15);
+        assert!(placed.iter().all(|idx| idx.col < 5));
+    }
+
+    #[test]
+    fn test_zone_and_listed_placement()
+    {
+        let map: _ = Grid::<()>::from_ascii("
+hh..
+h.zz
+").unwrap();
+        let (mut grid, zones): _ = map.into_parts();
+
+        let placement: Placement = Placement::in_zones(&zones, &['h']).unwrap();
+        let mut placed: Vec<PointIndex> = place_all(&mut grid, placement);
+        placed.sort_by_key(|idx| idx.as_dimensions());
+        assert_eq!(placed, zones[&'h']);
+
+        let unknown: PlacementError = Placement::in_zones(&zones, &['q']).unwrap_err();
+        assert_eq!(unknown.to_string(), "the map has no zone 'q'");
+
+        let listed: Placement = Placement::parse("# the far side\n1,3\n0 0\n1 2\n0 3\n").unwrap();
+        assert_eq!(place_all(&mut grid, listed), vec![
+            PointIndex::new(1,3),
+            PointIndex::new(1,2),
+            PointIndex::new(0,3)
+        ]);
+
+        assert!(Placement::parse("1 2 3").is_err());
+        let outside: Placement = Placement::parse("9 9").unwrap();
+        assert!(Spawner::new(&grid, outside).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use super::{PointState, Capacity};
+
+use std::collections;
+
+/// How a grid holds its per-point data.
+#[derive(Debug,Default,Clone,Copy,PartialEq)]
+pub enum Layout {
+    /// One entry per point, allocated up front.
+    #[default]
+    Dense,
+    /// Entries only where a point differs from its default, for huge and mostly empty worlds.
+    Sparse
+}
+
+/// One value per point, addressed by row-major flat index.
+///
+/// A sparse map treats every point it holds no entry for as `default`, so reads and
+/// writes behave exactly as they do on a dense map of the same length.
+#[derive(Debug,Clone,PartialEq)]
+pub enum PointMap<V> {
+    Dense(Vec<V>),
+    Sparse { default: V, values: collections::HashMap<usize, V> }
+}
+
+impl<V: Clone + PartialEq> PointMap<V>
+{
+    pub fn new(layout: Layout, len: usize, default: V) -> Self {
+        match layout {
+            Layout::Dense => PointMap::Dense(vec![default; len]),
+            Layout::Sparse => PointMap::Sparse { default, values: collections::HashMap::new() }
+        }
+    }
+
+    pub fn get(&self, flat_idx: usize) -> V {
+        match self {
+            PointMap::Dense(values) => values[flat_idx].clone(),
+            PointMap::Sparse { default, values } => values
+                .get(&flat_idx)
+                .unwrap_or(default)
+                .clone()
+        }
+    }
+
+    pub fn set(&mut self, flat_idx: usize, value: V) -> V {
+        match self {
+            PointMap::Dense(values) => std::mem::replace(&mut values[flat_idx], value),
+            PointMap::Sparse { default, values } => {
+                let old_value: Option<V> = if &value == default {
+                    values.remove(&flat_idx)
+                } else {
+                    values.insert(flat_idx, value)
+                };
+                old_value.unwrap_or_else(|| default.clone())
+            }
+        }
+    }
+
+    /// How many entries the map actually stores.
+    pub fn materialized(&self) -> usize {
+        match self {
+            PointMap::Dense(values) => values.len(),
+            PointMap::Sparse { values, .. } => values.len()
+        }
+    }
+
+    /// Every stored entry; points a sparse map holds no entry for are skipped.
+    pub fn iter(&self) -> Box<dyn Iterator<Item=(usize, &V)> + '_> {
+        match self {
+            PointMap::Dense(values) => Box::new(values.iter().enumerate()),
+            PointMap::Sparse { values, .. } => Box::new(values.iter().map(|(flat_idx, value)| (*flat_idx, value)))
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> Box<dyn Iterator<Item=(usize, &mut V)> + '_> {
+        match self {
+            PointMap::Dense(values) => Box::new(values.iter_mut().enumerate()),
+            PointMap::Sparse { values, .. } => Box::new(values.iter_mut().map(|(flat_idx, value)| (*flat_idx, value)))
+        }
+    }
+
+    /// Applies `f` to every stored entry, and to the default of a sparse map.
+    pub fn map_in_place<F: Fn(&V) -> V>(&mut self, f: F) {
+        match self {
+            PointMap::Dense(values) => {
+                for value in values.iter_mut() {
+                    *value = f(value)
+                }
+            },
+            PointMap::Sparse { default, values } => {
+                *default = f(default);
+                for value in values.values_mut() {
+                    *value = f(value)
+                }
+                let default: &V = default;
+                values.retain(|_, value| value != default)
+            }
+        }
+    }
+
+    /// The points an update seeded from the stored entries can touch: every point of a
+    /// dense map, or the stored entries of a sparse map together with their `spread`.
+    pub fn reach<S, I>(&self, spread: S) -> Vec<usize>
+    where
+        S: Fn(usize) -> I,
+        I: IntoIterator<Item=usize>
+    {
+        match self {
+            PointMap::Dense(values) => (0..values.len()).collect(),
+            PointMap::Sparse { values, .. } => values
+                .keys()
+                .flat_map(|flat_idx| std::iter::once(*flat_idx).chain(spread(*flat_idx)))
+                .collect::<collections::HashSet<usize>>()
+                .into_iter()
+                .collect()
+        }
+    }
+}
+
+// Slot ids below `FIRST_STATE` stand for the states that carry no occupants, 
+// so only occupied points take up room in `states`.
+const EMPTY_SLOT: u32 = 0;
+const CLAIMED_SLOT: u32 = 1;
+const BLOCKED_SLOT: u32 = 2;
+const FIRST_STATE: u32 = 3;
+
+/// The state of every point, kept as a dense or sparse array of slot ids into a slab that 
+/// only holds the states of occupied points.
+#[derive(Debug)]
+pub struct PointStates<T> {
+    slots: PointMap<u32>,
+    states: Vec<PointState<T>>,
+    vacant_states: Vec<u32>,
+    empty: PointState<T>,
+    claimed: PointState<T>,
+    blocked: PointState<T>
+}
+
+impl<T> PointStates<T>
+{
+    pub fn new(layout: Layout, len: usize) -> Self {
+        Self {
+            slots: PointMap::new(layout, len, EMPTY_SLOT),
+            states: Vec::new(),
+            vacant_states: Vec::new(),
+            empty: PointState::Empty,
+            claimed: PointState::Claimed,
+            blocked: PointState::Blocked
+        }
+    }
+
+    pub fn get(&self, flat_idx: usize) -> &PointState<T> {
+        match self.slots.get(flat_idx) {
+            EMPTY_SLOT => &self.empty,
+            CLAIMED_SLOT => &self.claimed,
+            BLOCKED_SLOT => &self.blocked,
+            slot => &self.states[(slot - FIRST_STATE) as usize]
+        }
+    }
+
+    pub fn replace(&mut self, flat_idx: usize, new_value: PointState<T>) -> PointState<T> {
+        let old_value: PointState<T> = self.take_slot(flat_idx);
+        let slot: u32 = self.store(new_value);
+        self.slots.set(flat_idx, slot);
+        old_value
+    }
+
+    /// How many occupied states the slab currently has room for.
+    pub fn stored(&self) -> usize {
+        self.states.len()
+    }
+
+    // Empties the slot, returning its state and recycling the storage it held.
+    fn take_slot(&mut self, flat_idx: usize) -> PointState<T> {
+        match self.slots.set(flat_idx, EMPTY_SLOT) {
+            EMPTY_SLOT => PointState::Empty,
+            CLAIMED_SLOT => PointState::Claimed,
+            BLOCKED_SLOT => PointState::Blocked,
+            slot => {
+                self.vacant_states.push(slot);
+                std::mem::replace(&mut self.states[(slot - FIRST_STATE) as usize], PointState::Empty)
+            }
+        }
+    }
+
+    fn store(&mut self, state: PointState<T>) -> u32 {
+        match state {
+            PointState::Empty => EMPTY_SLOT,
+            PointState::Claimed => CLAIMED_SLOT,
+            PointState::Blocked => BLOCKED_SLOT,
+            occupied => match self.vacant_states.pop() {
+                Some(slot) => {
+                    self.states[(slot - FIRST_STATE) as usize] = occupied;
+                    slot
+                },
+                None => {
+                    self.states.push(occupied);
+                    self.states.len() as u32 - 1 + FIRST_STATE
+                }
+            }
+        }
+    }
+}
+
+/// The state of every point together with how many occupants each may hold, enforcing the 
+/// occupancy rules that `Grid` and `VoxelGrid` share.
+#[derive(Debug)]
+pub struct PointStore<T> {
+    states: PointStates<T>,
+    capacity: Capacity,
+    capacities: collections::HashMap<usize, Capacity>
+}
+
+impl<T> PointStore<T>
+{
+    pub fn new(layout: Layout, len: usize, capacity: Capacity) -> Self {
+        Self {
+            states: PointStates::new(layout, len),
+            capacity,
+            capacities: collections::HashMap::new()
+        }
+    }
+
+    pub fn get(&self, flat_idx: usize) -> &PointState<T> {
+        self.states.get(flat_idx)
+    }
+
+    pub fn capacity(&self, flat_idx: usize) -> Capacity {
+        self.capacities
+            .get(&flat_idx)
+            .copied()
+            .unwrap_or(self.capacity)
+    }
+
+    /// Only points whose capacity differs from the default take up room in the table.
+    pub fn set_capacity(&mut self, flat_idx: usize, capacity: Capacity) {
+        if capacity == self.capacity {
+            self.capacities.remove(&flat_idx);
+        } else {
+            self.capacities.insert(flat_idx, capacity);
+        }
+    }
+
+    pub fn has_room(&self, flat_idx: usize) -> bool {
+        self.get(flat_idx).has_room(self.capacity(flat_idx))
+    }
+
+    pub fn replace(&mut self, flat_idx: usize, new_value: PointState<T>) -> PointState<T> {
+        self.states.replace(flat_idx, new_value)
+    }
+
+    /// Adds an occupant to the point, handing it back if the point is already at capacity.
+    pub fn admit(&mut self, flat_idx: usize, occupant: T) -> Result<(), T> 
+    {
+        if !self.has_room(flat_idx) {
+            return Err(occupant)
+        }
+        let new_state: PointState<T> = self.replace(flat_idx, PointState::Empty).admitting(occupant);
+        self.replace(flat_idx, new_state);
+        Ok(())
+    }
+
+    /// Removes and returns the first occupant of the point that satisfies `which`.
+    pub fn release_where<F>(&mut self, flat_idx: usize, which: F) -> Option<T> 
+    where
+        F: Fn(&T) -> bool
+    {
+        let (remaining, released): (PointState<T>, Option<T>) = self.replace(flat_idx, PointState::Empty).releasing(which);
+        self.replace(flat_idx, remaining);
+        released
+    }
+
+    /// Moves the first occupant of `from` satisfying `which` onto `to` if it has room.
+    pub fn transfer_where<F>(&mut self, from: usize, to: usize, which: F) -> bool 
+    where
+        F: Fn(&T) -> bool
+    {
+        if from == to || !self.has_room(to) {
+            return false
+        }
+        match self.release_where(from, which) {
+            Some(occupant) => self.admit(to, occupant).is_ok(),
+            None => false
+        }
+    }
+
+    /// How many occupied states the slab currently has room for.
+    pub fn stored(&self) -> usize {
+        self.states.stored()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_sparse_matches_dense()
+    {
+        let mut dense: PointMap<usize> = PointMap::new(Layout::Dense, 10, 0);
+        let mut sparse: PointMap<usize> = PointMap::new(Layout::Sparse, 10, 0);
+
+        for map in [&mut dense, &mut sparse].iter_mut() {
+            assert_eq!(map.set(3, 7), 0);
+            assert_eq!(map.set(3, 8), 7);
+            map.set(5, 1);
+            map.map_in_place(|value| value.saturating_sub(1));
+        }
+
+        for flat_idx in 0..10 {
+            assert_eq!(dense.get(flat_idx), sparse.get(flat_idx));
+        }
+        assert_eq!(sparse.materialized(), 1);
+        assert_eq!(dense.materialized(), 10);
+    }
+
+    #[test]
+    fn test_sparse_drops_default_values()
+    {
+        let mut sparse: PointMap<Option<usize>> = PointMap::new(Layout::Sparse, 1_000_000_000, None);
+
+        sparse.set(999_999_999, Some(1));
+        assert_eq!(sparse.materialized(), 1);
+        assert_eq!(sparse.set(999_999_999, None), Some(1));
+        assert_eq!(sparse.materialized(), 0);
+
+        sparse.set(4, Some(2));
+        let mut reach: Vec<usize> = sparse.reach(|flat_idx| vec![flat_idx - 1, flat_idx + 1]);
+        reach.sort();
+        assert_eq!(reach, vec![3, 4, 5]);
+    }
+}
@@ -0,0 +1,127 @@
+use super::{Grid, PointIndex, PointState};
+
+/// What the ground at a point is like to cross.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Terrain {
+    /// Costs one to enter.
+    Open,
+    /// Costs the given amount to enter, such as rubble or shallow water. Costs below one 
+    /// may lead A* to paths that are not the cheapest.
+    Rough(f64),
+    /// Can never be entered or occupied, such as a wall or a river.
+    Impassable
+}
+
+impl<T> Grid<T> 
+{
+    /// Lays `terrain` at `idx`, refusing to wall in a point that still holds occupants.
+    pub fn set_terrain(&mut self, idx: &PointIndex, terrain: Terrain) -> bool 
+    {
+        let flat_idx: usize = self.flat_index(idx);
+        match terrain {
+            Terrain::Impassable => {
+                if self.state(idx).occupancy() > 0 {
+                    return false
+                }
+                self.replace(idx, PointState::Blocked);
+                self.costs.set(flat_idx, 1.0);
+            },
+            Terrain::Open | Terrain::Rough(_) => {
+                if self.state(idx).is_blocked() {
+                    self.replace(idx, PointState::Empty);
+                }
+                let cost: f64 = match terrain {
+                    Terrain::Rough(cost) => f64::max(cost, 0.0),
+                    _ => 1.0
+                };
+                self.costs.set(flat_idx, cost);
+            }
+        }
+        true
+    }
+
+    pub fn terrain(&self, idx: &PointIndex) -> Terrain {
+        if self.state(idx).is_blocked() {
+            return Terrain::Impassable
+        }
+        match self.movement_cost(idx) {
+            cost if cost == 1.0 => Terrain::Open,
+            cost => Terrain::Rough(cost)
+        }
+    }
+
+    /// The cost of stepping onto `idx`, or infinity if it is impassable.
+    pub fn movement_cost(&self, idx: &PointIndex) -> f64 {
+        if self.state(idx).is_blocked() {
+            f64::INFINITY
+        } else {
+            self.costs.get(self.flat_index(idx))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use super::super::{Boundary, Obstacles, Heuristic, Capacity};
+
+    use crate::context::Space;
+
+    #[test]
+    fn test_impassable_points_hold_no_one() 
+    {
+        let mut grid: Grid<usize> = Grid::with_capacity(3,3,Capacity::Unbounded);
+        let (wall, floor): _ = (PointIndex::new(1,1), PointIndex::new(1,2));
+
+        assert!(grid.set_terrain(&wall, Terrain::Impassable));
+        assert_eq!(grid.terrain(&wall), Terrain::Impassable);
+        assert!(!grid.has_room(&wall));
+        assert_eq!(grid.admit(&wall, 0), Err(0));
+
+        grid.admit(&floor, 1).unwrap();
+        assert!(!grid.relocate(&floor, &wall, |_| true));
+        assert!(!grid.set_terrain(&floor, Terrain::Impassable));
+
+        assert!(grid.set_terrain(&wall, Terrain::Rough(3.0)));
+        assert_eq!(grid.terrain(&wall), Terrain::Rough(3.0));
+        assert!(grid.relocate(&floor, &wall, |_| true));
+    }
+
+    #[test]
+    fn test_paths_avoid_walls_and_weigh_rough_ground() 
+    {
+        let mut grid: Grid<()> = Grid::new(5,5).with_boundary(Boundary::Closed);
+        for row in 0..4 {
+            grid.set_terrain(&PointIndex::new(row,2), Terrain::Impassable);
+        }
+        let (from, to): _ = (PointIndex::new(0,0), PointIndex::new(0,4));
+
+        let path: _ = grid.bfs(&from, &to, Obstacles::default()).unwrap();
+        assert!(path.contains(&PointIndex::new(4,2)));
+        assert_eq!(grid.bfs(&from, &PointIndex::new(0,2), Obstacles::default()), None);
+
+        // rough ground at the only gap still has to be crossed
+        grid.set_terrain(&PointIndex::new(4,2), Terrain::Rough(20.0));
+        let path: _ = grid.astar(&from, &to, None, Heuristic::Manhattan, Obstacles::default()).unwrap();
+        assert!(path.contains(&PointIndex::new(4,2)));
+        assert_eq!(path.len(), 13);
+
+        grid.set_terrain(&PointIndex::new(4,2), Terrain::Impassable);
+        assert_eq!(grid.astar(&from, &to, None, Heuristic::Manhattan, Obstacles::default()), None);
+        assert_eq!(grid.distance_map(vec![to.clone()], Obstacles::default()).get(&from), None);
+    }
+
+    #[test]
+    fn test_astar_detours_around_rough_ground() 
+    {
+        let mut grid: Grid<()> = Grid::new(3,5).with_boundary(Boundary::Closed);
+        grid.set_terrain(&PointIndex::new(1,2), Terrain::Rough(10.0));
+
+        let path: _ = grid.astar(&PointIndex::new(1,0), &PointIndex::new(1,4), None, Heuristic::Manhattan, Obstacles::default()).unwrap();
+
+        assert!(!path.contains(&PointIndex::new(1,2)));
+        assert_eq!(path.len(), 7);
+    }
+}
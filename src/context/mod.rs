@@ -1,31 +1,42 @@
 pub mod grid;
+pub mod voxel;
+mod space;
+
+pub use space::{Space, Position};
 
 use crate::utils;
 use std::fmt;
 
 #[derive(Clone)]
 pub struct GridPosition<T> {
+    index: grid::PointIndex,
     tile: utils::Cell<grid::Point<T>>,
     grid: utils::Cell<grid::Grid<T>>
 }
 
 impl<T> GridPosition<T> {
-    pub fn new(tile: utils::Cell<grid::Point<T>>, grid: utils::Cell<grid::Grid<T>>) -> Self {
-        Self {tile, grid}
+    pub fn new(index: grid::PointIndex, grid: utils::Cell<grid::Grid<T>>) -> Self {
+        let tile: utils::Cell<grid::Point<T>> = grid::Point::new(index.clone(), std::rc::Rc::downgrade(&grid)).into_cell();
+        Self {index, tile, grid}
     }
 
     pub fn into_cell(self) -> utils::Cell<Self> {
         std::rc::Rc::new(std::cell::RefCell::new(self))
     }
 
-    pub fn get_tile(&self) -> utils::Cell<grid::Point<T>> {
-        self.tile.clone()
+    pub fn get_idx(&self) -> grid::PointIndex {
+        self.index.clone()
     }
 
     pub fn get_grid(&self) -> utils::Cell<grid::Grid<T>> {
         self.grid.clone()
     }
 
+    /// A handle on the point this position stands on.
+    pub fn get_tile(&self) -> utils::Cell<grid::Point<T>> {
+        self.tile.clone()
+    }
+
     pub fn borrow(&self) -> std::cell::Ref<grid::Point<T>> {
         self.tile.borrow()
     }
@@ -34,17 +45,181 @@ impl<T> GridPosition<T> {
         self.tile.borrow_mut()
     }
 
+    pub fn state(&self) -> std::cell::Ref<grid::PointState<T>> {
+        std::cell::Ref::map(self.grid.borrow(), |grid| grid.state(&self.index))
+    }
+
+    pub fn has_room(&self) -> bool {
+        self.grid
+            .borrow()
+            .point(&self.index)
+            .has_room()
+    }
+
+    pub fn replace(&self, new_value: grid::PointState<T>) -> grid::PointState<T> {
+        self.grid
+            .borrow_mut()
+            .replace(&self.index, new_value)
+    }
+
+    pub fn release_where<F>(&self, which: F) -> Option<T>
+    where
+        F: Fn(&T) -> bool
+    {
+        self.grid
+            .borrow_mut()
+            .release_where(&self.index, which)
+    }
+
+    /// Moves the first occupant satisfying `which` onto `new_position` if it has room.
+    pub fn transfer_where<F>(&self, new_position: &Self, which: F) -> bool
+    where
+        F: Fn(&T) -> bool
+    {
+        self.grid
+            .borrow_mut()
+            .transfer_where(&self.index, &new_position.index, which)
+    }
+
     pub fn get_dimensions(&self) -> (usize,usize) {
         self.grid.borrow().get_dimensions()
     }
+
+    /// The distance to `other` under the grid's boundary and metric.
+    pub fn distance_to(&self, other: &Self) -> grid::Distance {
+        self.grid
+            .borrow()
+            .distance(&self.index, &other.index)
+    }
+
+    /// Whether nothing impassable stands between this position and `other`.
+    pub fn in_sight_of(&self, other: &Self) -> bool {
+        self.grid
+            .borrow()
+            .line_of_sight(&self.index, &other.index)
+    }
+
+    /// Finds whoever stands within a radius of `position`, wherever it has since moved, 
+    /// as a locator for `message::Recipients::Within`.
+    pub fn locator<I,F>(position: utils::Cell<Self>, identify: F) -> impl Fn(usize) -> Vec<I>
+    where
+        T: Clone,
+        F: Fn(&T) -> I
+    {
+        move |radius| {
+            let position: std::cell::Ref<Self> = position.borrow();
+            let nearby: Vec<(grid::PointIndex, T)> = position.grid
+                .borrow()
+                .within_radius(&position.index, radius);
+            nearby.iter()
+                .map(|(_, occupant)| identify(occupant))
+                .collect()
+        }
+    }
+
+    pub fn read_field(&self, name: &str) -> Option<grid::FieldValue> {
+        self.grid
+            .borrow()
+            .read_field(name, &self.index)
+    }
+
+    pub fn write_field(&self, name: &str, value: grid::FieldValue) -> Option<grid::FieldValue> {
+        self.grid
+            .borrow_mut()
+            .write_field(name, &self.index, value)
+    }
+}
+
+impl<T> Position for GridPosition<T> 
+{
+    type Space = grid::Grid<T>;
+
+    fn get_index(&self) -> grid::PointIndex {
+        self.get_idx()
+    }
+
+    fn get_space(&self) -> utils::Cell<grid::Grid<T>> {
+        self.get_grid()
+    }
 }
 
 impl<T:fmt::Debug> fmt::Debug for GridPosition<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "Position: {:?}",
-            self.tile
-        )
+        // the grid may be mid-update when a position is logged
+        match self.grid.try_borrow() {
+            Ok(grid) => write!(f, "Position: {:?}", grid.point(&self.index)),
+            Err(_) => write!(f, "Position: {:?}", self.index)
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct VoxelPosition<T> {
+    index: voxel::VoxelIndex,
+    voxels: utils::Cell<voxel::VoxelGrid<T>>
+}
+
+impl<T> VoxelPosition<T> {
+    pub fn new(index: voxel::VoxelIndex, voxels: utils::Cell<voxel::VoxelGrid<T>>) -> Self {
+        Self {index, voxels}
+    }
+
+    pub fn into_cell(self) -> utils::Cell<Self> {
+        std::rc::Rc::new(std::cell::RefCell::new(self))
+    }
+
+    pub fn get_idx(&self) -> voxel::VoxelIndex {
+        self.index.clone()
+    }
+
+    pub fn state(&self) -> std::cell::Ref<grid::PointState<T>> {
+        std::cell::Ref::map(self.voxels.borrow(), |voxels| voxels.state(&self.index))
+    }
+
+    pub fn replace(&self, new_value: grid::PointState<T>) -> grid::PointState<T> {
+        self.voxels
+            .borrow_mut()
+            .replace(&self.index, new_value)
+    }
+
+    pub fn release_where<F>(&self, which: F) -> Option<T>
+    where
+        F: Fn(&T) -> bool
+    {
+        self.voxels
+            .borrow_mut()
+            .release_where(&self.index, which)
+    }
+
+    /// Moves the first occupant satisfying `which` onto `new_position` if it has room.
+    pub fn transfer_where<F>(&self, new_position: &Self, which: F) -> bool
+    where
+        F: Fn(&T) -> bool
+    {
+        self.voxels
+            .borrow_mut()
+            .transfer_where(&self.index, &new_position.index, which)
+    }
+}
+
+impl<T> Position for VoxelPosition<T> 
+{
+    type Space = voxel::VoxelGrid<T>;
+
+    fn get_index(&self) -> voxel::VoxelIndex {
+        self.get_idx()
+    }
+
+    fn get_space(&self) -> utils::Cell<voxel::VoxelGrid<T>> {
+        self.voxels.clone()
+    }
+}
+
+impl<T:fmt::Debug> fmt::Debug for VoxelPosition<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.voxels.try_borrow() {
+            Ok(voxels) => write!(f, "Position: {:?} Voxel {:?}", voxels.state(&self.index), self.index),
+            Err(_) => write!(f, "Position: {:?}", self.index)
+        }
     }
 }
@@ -0,0 +1,94 @@
+use crate::utils::Cell;
+
+/// A topology that agents can be placed into and move around.
+///
+/// `Grid` is the reference implementation, but any lattice or graph that can
+/// answer these queries by index can stand in for it.
+pub trait Space 
+{
+    type Index: Clone + PartialEq;
+
+    type Position: Position<Space=Self>;
+
+    type Occupant;
+
+    type Distance: Ord + Copy;
+
+    fn neighbours_of(&self, idx: &Self::Index) -> Vec<Self::Index>;
+
+    fn distance_between(&self, lhs: &Self::Index, rhs: &Self::Index) -> Self::Distance;
+
+    fn is_vacant(&self, idx: &Self::Index) -> bool;
+
+    fn has_room(&self, idx: &Self::Index) -> bool;
+
+    fn occupants_of(&self, idx: &Self::Index) -> Vec<Self::Occupant>
+    where
+        Self::Occupant: Clone;
+
+    /// Moves the first occupant of `from` satisfying `which` onto `to`, returning false if 
+    /// there was no such occupant or `to` had no room for it.
+    fn relocate<F>(&mut self, from: &Self::Index, to: &Self::Index, which: F) -> bool
+    where
+        F: Fn(&Self::Occupant) -> bool;
+}
+
+/// A handle on a single location within a `Space`.
+pub trait Position 
+{
+    type Space: Space;
+
+    fn get_index(&self) -> <Self::Space as Space>::Index;
+
+    fn get_space(&self) -> Cell<Self::Space>;
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use crate::context::grid::{Grid, PointIndex, PointState, Capacity};
+
+    #[test]
+    fn test_grid_neighbours_of() 
+    {
+        let grid: Grid<()> = Grid::new(5,5);
+        let source_idx: _ = PointIndex::new(2,2);
+
+        let test_neighbours: _ = grid.neighbours_of(&source_idx);
+        let expected_neighbours: Vec<_> = vec![
+            PointIndex::new(1,2),
+            PointIndex::new(2,3),
+            PointIndex::new(3,2),
+            PointIndex::new(2,1)
+        ];
+
+        assert_eq!(test_neighbours, expected_neighbours);
+    }
+
+    #[test]
+    fn test_grid_relocate() 
+    {
+        let mut grid: Grid<usize> = Grid::new(5,5);
+        let from: _ = PointIndex::new(2,2);
+        let to: _ = PointIndex::new(2,3);
+
+        grid.replace(&from, PointState::Occupied(7));
+
+        assert!(grid.relocate(&from, &to, |_| true));
+        assert!(grid.is_vacant(&from));
+        assert_eq!(grid.occupants_of(&to), vec![7]);
+
+        grid.replace(&from, PointState::Occupied(8));
+
+        assert!(!grid.relocate(&from, &to, |_| true));
+        assert_eq!(grid.occupants_of(&from), vec![8]);
+
+        grid.set_capacity(&to, Capacity::Unbounded);
+
+        assert!(!grid.relocate(&from, &to, |occupant| *occupant == 7));
+        assert!(grid.relocate(&from, &to, |occupant| *occupant == 8));
+        assert_eq!(grid.occupants_of(&to), vec![7, 8]);
+    }
+}
@@ -0,0 +1,326 @@
+use std::{rc,cell,fmt};
+
+use crate::{utils,context};
+
+use utils::Cell;
+
+use super::grid::{Boundary, Capacity, Distance, Layout, Metric, PointState, PointStore};
+
+/// Which of the surrounding voxels count as neighbours.
+#[derive(Debug,Default,Clone,Copy,PartialEq)]
+pub enum Neighbourhood {
+    /// Voxels sharing a face.
+    #[default]
+    Six,
+    /// Voxels sharing a face or an edge.
+    Eighteen,
+    /// Voxels sharing a face, an edge or a corner.
+    TwentySix
+}
+
+impl Neighbourhood {
+    // Every offset to a neighbour, in x-fastest order.
+    fn offsets(self) -> Vec<(isize,isize,isize)>
+    {
+        let most_axes: usize = match self {
+            Neighbourhood::Six => 1,
+            Neighbourhood::Eighteen => 2,
+            Neighbourhood::TwentySix => 3
+        };
+        let mut offsets: Vec<(isize,isize,isize)> = Vec::new();
+
+        for dz in -1..=1isize {
+            for dy in -1..=1isize {
+                for dx in -1..=1isize {
+                    let moved_axes: usize = [dx, dy, dz].iter().filter(|delta| **delta != 0).count();
+                    if moved_axes > 0 && moved_axes <= most_axes {
+                        offsets.push((dx, dy, dz))
+                    }
+                }
+            }
+        }
+        offsets
+    }
+}
+
+/// A three-dimensional lattice with the same occupancy rules as `Grid`.
+#[derive(Debug)]
+pub struct VoxelGrid<T> {
+    x: usize,
+    y: usize,
+    z: usize,
+    boundary: Boundary,
+    metric: Metric,
+    neighbourhood: Neighbourhood,
+    points: PointStore<T>
+}
+
+impl<T> VoxelGrid<T>
+{
+    pub fn new(x: usize, y: usize, z: usize) -> Self {
+        Self::with_capacity(x, y, z, Capacity::Bounded(1))
+    }
+
+    /// Builds a lattice where every voxel starts out accepting up to `capacity` occupants.
+    pub fn with_capacity(x: usize, y: usize, z: usize, capacity: Capacity) -> Self {
+        Self::with_layout(x, y, z, capacity, Layout::Dense)
+    }
+
+    pub fn with_layout(x: usize, y: usize, z: usize, capacity: Capacity, layout: Layout) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            boundary: Boundary::default(),
+            metric: Metric::default(),
+            neighbourhood: Neighbourhood::default(),
+            points: PointStore::new(layout, x * y * z, capacity)
+        }
+    }
+
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    pub fn with_metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn with_neighbourhood(mut self, neighbourhood: Neighbourhood) -> Self {
+        self.neighbourhood = neighbourhood;
+        self
+    }
+
+    pub fn get_dimensions(&self) -> (usize,usize,usize) {
+        (self.x, self.y, self.z)
+    }
+
+    pub fn into_cell(self) -> Cell<Self> {
+        rc::Rc::new(cell::RefCell::new(self))
+    }
+
+    fn flat_index(&self, idx: &VoxelIndex) -> usize {
+        (idx.z * self.y + idx.y) * self.x + idx.x
+    }
+
+    /// The neighbours of `idx` under the lattice's neighbourhood, skipping any beyond a closed edge.
+    pub fn get_connections(&self, idx: &VoxelIndex) -> Vec<VoxelIndex> {
+        let boundary: Boundary = self.boundary;
+        let mut connections: Vec<VoxelIndex> = Vec::new();
+
+        for (dx, dy, dz) in self.neighbourhood.offsets().into_iter() {
+            let shifted: _ = (
+                boundary.shift(idx.x, dx, self.x),
+                boundary.shift(idx.y, dy, self.y),
+                boundary.shift(idx.z, dz, self.z)
+            );
+            if let (Some(x), Some(y), Some(z)) = shifted {
+                let neighbour: VoxelIndex = VoxelIndex::new(x, y, z);
+                // wrapping on an axis shorter than three can land on the same voxel twice
+                if &neighbour != idx && !connections.contains(&neighbour) {
+                    connections.push(neighbour)
+                }
+            }
+        }
+        connections
+    }
+
+    pub fn distance(&self, lhs: &VoxelIndex, rhs: &VoxelIndex) -> Distance {
+        self.metric.measure_axes(&[
+            self.boundary.delta(lhs.x, rhs.x, self.x),
+            self.boundary.delta(lhs.y, rhs.y, self.y),
+            self.boundary.delta(lhs.z, rhs.z, self.z)
+        ])
+    }
+
+    pub fn state(&self, idx: &VoxelIndex) -> &PointState<T> {
+        self.points.get(self.flat_index(idx))
+    }
+
+    pub fn capacity(&self, idx: &VoxelIndex) -> Capacity {
+        self.points.capacity(self.flat_index(idx))
+    }
+
+    pub fn set_capacity(&mut self, idx: &VoxelIndex, capacity: Capacity) {
+        let flat_idx: usize = self.flat_index(idx);
+        self.points.set_capacity(flat_idx, capacity)
+    }
+
+    pub fn replace(&mut self, idx: &VoxelIndex, new_value: PointState<T>) -> PointState<T> {
+        let flat_idx: usize = self.flat_index(idx);
+        self.points.replace(flat_idx, new_value)
+    }
+
+    /// Adds an occupant to the voxel, handing it back if the voxel is already at capacity.
+    pub fn admit(&mut self, idx: &VoxelIndex, occupant: T) -> Result<(), T> {
+        let flat_idx: usize = self.flat_index(idx);
+        self.points.admit(flat_idx, occupant)
+    }
+
+    /// Removes and returns the first occupant at `idx` that satisfies `which`.
+    pub fn release_where<F>(&mut self, idx: &VoxelIndex, which: F) -> Option<T>
+    where
+        F: Fn(&T) -> bool
+    {
+        let flat_idx: usize = self.flat_index(idx);
+        self.points.release_where(flat_idx, which)
+    }
+
+    /// Moves the first occupant of `from` satisfying `which` onto `to` if it has room.
+    pub fn transfer_where<F>(&mut self, from: &VoxelIndex, to: &VoxelIndex, which: F) -> bool
+    where
+        F: Fn(&T) -> bool
+    {
+        let (from_idx, to_idx): (usize,usize) = (self.flat_index(from), self.flat_index(to));
+        self.points.transfer_where(from_idx, to_idx, which)
+    }
+}
+
+impl<T> context::Space for VoxelGrid<T>
+{
+    type Index = VoxelIndex;
+    type Position = context::VoxelPosition<T>;
+    type Occupant = T;
+    type Distance = Distance;
+
+    fn neighbours_of(&self, idx: &VoxelIndex) -> Vec<VoxelIndex> {
+        self.get_connections(idx)
+    }
+
+    fn distance_between(&self, lhs: &VoxelIndex, rhs: &VoxelIndex) -> Distance {
+        self.distance(lhs, rhs)
+    }
+
+    fn is_vacant(&self, idx: &VoxelIndex) -> bool {
+        self.state(idx).is_empty()
+    }
+
+    fn has_room(&self, idx: &VoxelIndex) -> bool {
+        self.points.has_room(self.flat_index(idx))
+    }
+
+    fn occupants_of(&self, idx: &VoxelIndex) -> Vec<T>
+    where
+        T: Clone
+    {
+        self.state(idx)
+            .occupants()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    fn relocate<F>(&mut self, from: &VoxelIndex, to: &VoxelIndex, which: F) -> bool
+    where
+        F: Fn(&T) -> bool
+    {
+        self.transfer_where(from, to, which)
+    }
+}
+
+#[derive(PartialEq,Eq,Hash,Clone)]
+pub struct VoxelIndex {
+    x: usize,
+    y: usize,
+    z: usize
+}
+
+impl VoxelIndex {
+    pub fn new(x: usize, y: usize, z: usize) -> Self {
+        Self {x,y,z}
+    }
+
+    pub fn as_dimensions(&self) -> (usize,usize,usize) {
+        (self.x, self.y, self.z)
+    }
+}
+
+impl fmt::Debug for VoxelIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({},{},{})", self.x, self.y, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    use context::Space;
+
+    #[test]
+    fn test_neighbourhood_sizes()
+    {
+        for (neighbourhood, expected) in [
+            (Neighbourhood::Six, 6),
+            (Neighbourhood::Eighteen, 18),
+            (Neighbourhood::TwentySix, 26)
+        ].iter() {
+            let voxels: VoxelGrid<()> = VoxelGrid::new(5,5,5).with_neighbourhood(*neighbourhood);
+            assert_eq!(voxels.get_connections(&VoxelIndex::new(2,2,2)).len(), *expected);
+            assert_eq!(voxels.get_connections(&VoxelIndex::new(0,0,0)).len(), *expected);
+
+            let closed: VoxelGrid<()> = VoxelGrid::new(5,5,5)
+                .with_neighbourhood(*neighbourhood)
+                .with_boundary(Boundary::Closed);
+            assert_eq!(closed.get_connections(&VoxelIndex::new(2,2,2)).len(), *expected);
+        }
+    }
+
+    #[test]
+    fn test_closed_corner_connections()
+    {
+        let voxels: VoxelGrid<()> = VoxelGrid::new(4,4,3).with_boundary(Boundary::Closed);
+
+        assert_eq!(voxels.get_connections(&VoxelIndex::new(0,0,0)), vec![
+            VoxelIndex::new(1,0,0),
+            VoxelIndex::new(0,1,0),
+            VoxelIndex::new(0,0,1)
+        ]);
+
+        let diagonal: VoxelGrid<()> = VoxelGrid::new(4,4,3)
+            .with_boundary(Boundary::Closed)
+            .with_neighbourhood(Neighbourhood::TwentySix);
+        assert_eq!(diagonal.get_connections(&VoxelIndex::new(0,0,0)).len(), 7);
+    }
+
+    #[test]
+    fn test_voxel_distance()
+    {
+        let voxels: VoxelGrid<()> = VoxelGrid::new(10,10,4);
+        let (lhs, rhs): _ = (VoxelIndex::new(0,0,0), VoxelIndex::new(9,2,2));
+
+        assert_eq!(voxels.distance(&lhs, &rhs), Distance::squared(9));
+        assert!(voxels.distance(&lhs, &rhs).within(3));
+
+        let closed: VoxelGrid<()> = VoxelGrid::new(10,10,4)
+            .with_boundary(Boundary::Closed)
+            .with_metric(Metric::Manhattan);
+        assert_eq!(closed.distance(&lhs, &rhs), Distance::exact(13));
+    }
+
+    #[test]
+    fn test_voxel_occupancy()
+    {
+        let mut voxels: VoxelGrid<usize> = VoxelGrid::new(3,3,3);
+        let (stairwell, landing): _ = (VoxelIndex::new(1,1,0), VoxelIndex::new(1,1,1));
+        voxels.set_capacity(&landing, Capacity::Bounded(2));
+
+        voxels.admit(&stairwell, 0).unwrap();
+        assert_eq!(voxels.admit(&stairwell, 1), Err(1));
+
+        voxels.replace(&VoxelIndex::new(0,0,0), PointState::Occupied(1));
+        voxels.replace(&VoxelIndex::new(2,2,2), PointState::Occupied(2));
+        assert!(voxels.relocate(&stairwell, &landing, |_| true));
+        assert!(voxels.relocate(&VoxelIndex::new(0,0,0), &landing, |_| true));
+        assert!(!voxels.relocate(&VoxelIndex::new(2,2,2), &landing, |_| true));
+
+        assert_eq!(voxels.occupants_of(&landing), vec![0, 1]);
+        assert!(voxels.is_vacant(&stairwell));
+
+        voxels.replace(&stairwell, PointState::Claimed);
+        assert!(!voxels.has_room(&stairwell));
+    }
+}
@@ -13,7 +13,8 @@ pub trait Seed
 
     type Kind;
 
-    fn seed(&mut self) ->  Self::Output;
+    /// `None` when nothing more can be seeded, such as when the world has no room left.
+    fn seed(&mut self) -> Option<Self::Output>;
 
     fn kind(&self) -> Self::Kind;
 
@@ -41,7 +42,7 @@ where
         {            
             let agents: Vec<T> = generator.quantity()
                 .map(|_| generator.seed())
-                .collect();
+                .collect::<Option<_>>()?;
                 
             self.insert_agents(generator.kind(), agents)            
         }
@@ -113,10 +114,10 @@ mod tests
         type Output = TestAgent;
         type Kind = TestKind;
 
-        fn seed(&mut self) -> Self::Output {
+        fn seed(&mut self) -> Option<Self::Output> {
             let id = self.count;
             self.count += 1;
-            TestAgent { id }
+            Some(TestAgent { id })
         }    
 
         fn kind(&self) -> TestKind {
@@ -128,6 +129,30 @@ mod tests
         }
     }
 
+    // runs out of room after `room` agents
+    struct CrampedSeed {
+        room: usize
+    }
+
+    impl Seed for CrampedSeed
+    {
+        type Output = TestAgent;
+        type Kind = TestKind;
+
+        fn seed(&mut self) -> Option<Self::Output> {
+            self.room = self.room.checked_sub(1)?;
+            Some(TestAgent::default())
+        }
+
+        fn kind(&self) -> TestKind {
+            TestKind::A
+        }
+
+        fn quantity(&self) -> std::ops::Range<usize> {
+            0..10
+        }
+    }
+
     #[derive(Debug,Clone,PartialEq,Eq,Hash)]
     enum TestKind {
         A,
@@ -197,4 +222,19 @@ mod tests
 
         assert_eq!(populated_environment.agents.0, expected_agent_storge);
     }
+
+    #[test]
+    fn test_exhausted_seed_fails_population()
+    {
+        let mut config: _ = config::Configuration::default();
+        config.insert_dummy_population_field(super::Demographics(vec![
+            Box::new(CrampedSeed { room: 5 })
+        ]));
+
+        let environment: Environment<TestKind,TestAgent> = Environment {
+            agents: Storage(Default::default()),
+            config
+        };
+        assert!(environment.populate().is_err());
+    }
 }
\ No newline at end of file
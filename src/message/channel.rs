@@ -0,0 +1,107 @@
+use std::fmt;
+
+use rand::{Rng, rngs};
+
+/// Faults injected into messages on their way from sender to recipient, drawn from the 
+/// generator the `AddressCollection` is seeded with so that a run can be replayed exactly.
+pub struct Channel<M> {
+    drop: f64,
+    duplication: Option<(f64, fn(&M) -> M)>,
+    reordering: f64,
+    corruption: Option<(f64, Box<dyn Fn(M, &mut rngs::StdRng) -> M>)>
+}
+
+// Panics on a probability outside [0, 1], NaN included, rather than when the first message is sent.
+fn checked(fault: &str, probability: f64) -> f64 {
+    assert!((0.0..=1.0).contains(&probability), "{} probability must lie in [0, 1], got {}", fault, probability);
+    probability
+}
+
+impl<M> Channel<M>
+{
+    /// A channel that delivers everything intact until faults are added.
+    pub fn new() -> Self {
+        Self {
+            drop: 0.0,
+            duplication: None,
+            reordering: 0.0,
+            corruption: None
+        }
+    }
+
+    /// Loses each message with the given probability.
+    ///
+    /// Every fault panics on a probability outside [0, 1].
+    pub fn with_drop(mut self, probability: f64) -> Self {
+        self.drop = checked("drop", probability);
+        self
+    }
+
+    /// Delivers each message twice with the given probability.
+    pub fn with_duplication(mut self, probability: f64) -> Self
+    where
+        M: Clone
+    {
+        self.duplication = Some((checked("duplication", probability), M::clone));
+        self
+    }
+
+    /// Holds each message back behind a random number of those sent after it, with the given probability.
+    pub fn with_reordering(mut self, probability: f64) -> Self {
+        self.reordering = checked("reordering", probability);
+        self
+    }
+
+    /// Passes each message through `corrupt` with the given probability.
+    pub fn with_corruption<F>(mut self, probability: f64, corrupt: F) -> Self
+    where
+        F: Fn(M, &mut rngs::StdRng) -> M + 'static
+    {
+        self.corruption = Some((checked("corruption", probability), Box::new(corrupt)));
+        self
+    }
+
+    /// The copies of `message` that make it through, none if it was dropped.
+    pub(super) fn transmit(&self, rng: &mut rngs::StdRng, message: M) -> Vec<M>
+    {
+        if rng.gen_bool(self.drop) {
+            return Vec::new()
+        }
+        let message: M = match &self.corruption {
+            Some((probability, corrupt)) if rng.gen_bool(*probability) => corrupt(message, rng),
+            _ => message
+        };
+        match self.duplication {
+            Some((probability, duplicate)) if rng.gen_bool(probability) => vec![duplicate(&message), message],
+            _ => vec![message]
+        }
+    }
+
+    /// How many places further back a message sent with `behind` others after it ends up.
+    pub(super) fn holdback(&self, rng: &mut rngs::StdRng, behind: usize) -> usize {
+        if behind > 0 && rng.gen_bool(self.reordering) {
+            rng.gen_range(1, behind + 1)
+        } else {
+            0
+        }
+    }
+}
+
+impl<M> Default for Channel<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> fmt::Debug for Channel<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Channel {{ drop: {}, duplication: {:?}, reordering: {}, corruption: {:?} }}",
+            self.drop,
+            self.duplication.as_ref().map(|(probability, _)| probability),
+            self.reordering,
+            self.corruption.as_ref().map(|(probability, _)| probability)
+        )
+    }
+}
@@ -0,0 +1,182 @@
+use std::{time, fmt, cmp};
+
+use rand::{Rng, rngs, distributions};
+
+/// How outboxes deliver through the `AddressCollection` they share.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Delivery {
+    /// How long to wait on a recipient's inbox before the attempt times out.
+    pub lock_timeout: time::Duration,
+    pub retry: RetryPolicy,
+    /// Keeps the messages given up on in the collection's dead-letter queue instead of
+    /// handing them back in the send's error.
+    pub dead_letters: bool
+}
+
+impl Default for Delivery {
+    fn default() -> Self {
+        Self {
+            lock_timeout: time::Duration::from_millis(100),
+            retry: RetryPolicy::default(),
+            dead_letters: false
+        }
+    }
+}
+
+/// What happens to a message whose recipient's inbox could not be locked in time.
+#[derive(Debug,Default,Clone,Copy,PartialEq)]
+pub struct RetryPolicy {
+    /// How many later sends a message still undelivered is carried over to before it is given 
+    /// up on; the default gives up on the first timeout.
+    pub requeues: usize,
+    /// How many sends, or ticks for a message in transit, a message waits after its first
+    /// timeout, doubling after every further one; zero retries on the very next.
+    pub backoff_ticks: u64
+}
+
+impl RetryPolicy {
+    /// How many sends or ticks a message carried over `requeues` times already waits before its next try.
+    pub fn backoff(&self, requeues: usize) -> u64 {
+        let doubling: u64 = 1u64.checked_shl(requeues as u32).unwrap_or(u64::MAX);
+        u64::max(self.backoff_ticks.saturating_mul(doubling), 1)
+    }
+}
+
+/// How many ticks a message spends in transit before it reaches its recipient's inbox.
+#[derive(Default)]
+pub enum Latency<I> {
+    /// Delivered within the send itself.
+    #[default]
+    Instant,
+    Fixed(u64),
+    /// Anywhere from `min` to `max` ticks, inclusive, uniformly at random from the collection's 
+    /// seeded generator.
+    Uniform { min: u64, max: u64 },
+    /// `base` ticks and `per_unit` more for every unit of distance between sender and recipient, 
+    /// as measured by `distance`; agents it cannot place are treated as side by side.
+    Distance { base: u64, per_unit: f64, distance: Box<dyn Fn(&I,&I) -> Option<f64>> }
+}
+
+impl<I> Latency<I> 
+{
+    pub fn by_distance<F>(base: u64, per_unit: f64, distance: F) -> Self
+    where
+        F: Fn(&I,&I) -> Option<f64> + 'static
+    {
+        Latency::Distance { base, per_unit, distance: Box::new(distance) }
+    }
+
+    /// Panics if `min` is above `max`.
+    pub fn uniform(min: u64, max: u64) -> Self {
+        Latency::Uniform { min, max }.checked()
+    }
+
+    pub(super) fn checked(self) -> Self {
+        if let Latency::Uniform { min, max } = self {
+            assert!(min <= max, "uniform latency needs min <= max, got {}..={}", min, max);
+        }
+        self
+    }
+
+    pub(super) fn delay(&self, rng: &mut rngs::StdRng, sender_id: &I, recipient_id: &I) -> u64 {
+        match self {
+            Latency::Instant => 0,
+            Latency::Fixed(ticks) => *ticks,
+            Latency::Uniform { min, max } => rng.sample(distributions::Uniform::new_inclusive(*min, *max)),
+            Latency::Distance { base, per_unit, distance } => {
+                let units: f64 = distance(sender_id, recipient_id).unwrap_or(0.0);
+                base + (units * per_unit).round() as u64
+            }
+        }
+    }
+}
+
+impl<I> fmt::Debug for Latency<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Latency::Instant => write!(f, "Instant"),
+            Latency::Fixed(ticks) => write!(f, "Fixed({})", ticks),
+            Latency::Uniform { min, max } => write!(f, "Uniform({}..={})", min, max),
+            Latency::Distance { base, per_unit, .. } => write!(f, "Distance({} + {}/unit)", base, per_unit)
+        }
+    }
+}
+
+/// How far a message carries from its sender. Agents `distance` cannot place are always in range.
+pub struct Range<I> {
+    limit: f64,
+    silent: bool,
+    distance: Box<dyn Fn(&I,&I) -> Option<f64>>,
+    sight: Option<Box<dyn Fn(&I,&I) -> bool>>
+}
+
+impl<I> Range<I>
+{
+    /// Carries messages no further than `limit`, as measured by `distance`.
+    pub fn new<F>(limit: f64, distance: F) -> Self
+    where
+        F: Fn(&I,&I) -> Option<f64> + 'static
+    {
+        Self { limit, silent: false, distance: Box::new(distance), sight: None }
+    }
+
+    /// Also stops messages whose recipient `sight` says the sender cannot see.
+    pub fn with_line_of_sight<F>(mut self, sight: F) -> Self
+    where
+        F: Fn(&I,&I) -> bool + 'static
+    {
+        self.sight = Some(Box::new(sight));
+        self
+    }
+
+    /// Loses messages that do not carry instead of handing them back as `OutOfRange`.
+    pub fn silently(mut self) -> Self {
+        self.silent = true;
+        self
+    }
+
+    pub(super) fn is_silent(&self) -> bool {
+        self.silent
+    }
+
+    pub(super) fn carries(&self, sender_id: &I, recipient_id: &I) -> bool {
+        let within: bool = (self.distance)(sender_id, recipient_id).map_or(true, |units| units <= self.limit);
+        within && self.sight.as_ref().map_or(true, |sight| sight(sender_id, recipient_id))
+    }
+}
+
+impl<I> fmt::Debug for Range<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Range {{ limit: {}, silent: {}, line_of_sight: {} }}", self.limit, self.silent, self.sight.is_some())
+    }
+}
+
+/// A message on its way, ordered so that a `BinaryHeap` pops the earliest arrival first.
+pub(super) struct InTransit<I,M> {
+    pub(super) due: u64,
+    pub(super) sequence: u64,
+    pub(super) sender_id: I,
+    pub(super) recipient_id: I,
+    pub(super) message: M,
+    pub(super) requeues: usize
+}
+
+impl<I,M> Ord for InTransit<I,M> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (other.due, other.sequence).cmp(&(self.due, self.sequence))
+    }
+}
+
+impl<I,M> PartialOrd for InTransit<I,M> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<I,M> PartialEq for InTransit<I,M> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.due, self.sequence) == (other.due, other.sequence)
+    }
+}
+
+impl<I,M> Eq for InTransit<I,M> { }
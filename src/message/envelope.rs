@@ -0,0 +1,79 @@
+use super::{Priority, Measure};
+
+/// Identifies a message sealed in an `Envelope`, unique within its `AddressCollection`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct MessageId(pub(super) u64);
+
+/// Ties a request to every reply that follows it; it shares its number with the opening message.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub struct ConversationId(pub(super) u64);
+
+/// A message with the addressing and correlation details multi-round protocols need. Use it as
+/// the message type of the mailboxes to get `AgentOutbox::request` and `AgentInbox::replies_to`.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Envelope<I,M> {
+    pub(super) id: MessageId,
+    pub(super) conversation: ConversationId,
+    pub(super) reply_to: Option<MessageId>,
+    pub(super) sender_id: I,
+    pub(super) recipient_id: I,
+    pub(super) sent_at: u64,
+    pub(super) contents: M
+}
+
+impl<I,M> Envelope<I,M>
+{
+    pub fn get_id(&self) -> MessageId {
+        self.id
+    }
+
+    pub fn get_conversation(&self) -> ConversationId {
+        self.conversation
+    }
+
+    /// The message this one answers, if it is a reply.
+    pub fn get_reply_to(&self) -> Option<MessageId> {
+        self.reply_to
+    }
+
+    pub fn get_sender_id(&self) -> &I {
+        &self.sender_id
+    }
+
+    pub fn get_recipient_id(&self) -> &I {
+        &self.recipient_id
+    }
+
+    /// The collection's clock when the message was sealed.
+    pub fn get_sent_at(&self) -> u64 {
+        self.sent_at
+    }
+
+    pub fn get_contents(&self) -> &M {
+        &self.contents
+    }
+
+    pub fn into_contents(self) -> M {
+        self.contents
+    }
+}
+
+impl<I,M: Priority> Priority for Envelope<I,M>
+{
+    fn priority(&self) -> isize {
+        self.contents.priority()
+    }
+}
+
+impl<I,M: Measure> Measure for Envelope<I,M>
+{
+    fn label(&self) -> &'static str {
+        self.contents.label()
+    }
+
+    // the contents and the addressing around them
+    fn size(&self) -> Option<usize> {
+        let header: usize = std::mem::size_of::<Self>() - std::mem::size_of::<M>();
+        self.contents.size().map(|size| size + header)
+    }
+}
@@ -19,7 +19,15 @@ where
     M: fmt::Debug
 {  
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", &self.error_repr)
+        write!(f, "{}: {:?}", self.kind().as_str(), &self.error_repr)
+    }
+}
+
+impl<I,M> MessageError<I,M> {
+    pub fn kind(&self) -> &MessageErrorKind<I,M> {
+        match &self.error_repr {
+            ErrorRepr::Simple(error_kind) => error_kind
+        }
     }
 }
 
@@ -51,18 +59,46 @@ pub struct CustomError<I,M> {
 
 #[derive(Debug)]
 pub enum MessageErrorKind<I,M> {
-    // LockTimedOut(AbortedMessage<I,M>),
-    AbortedMessages(Vec<AbortedMessage<I,M>>)
+    LockTimedOut(AbortedMessage<I,M>),
+    /// The recipient was never registered in the `AddressCollection`.
+    UnknownRecipient(AbortedMessage<I,M>),
+    /// The recipient has been removed from the `AddressCollection`.
+    RecipientDead(AbortedMessage<I,M>),
+    /// The recipient was out of the sender's reach under the collection's `Range`.
+    OutOfRange(AbortedMessage<I,M>),
+    /// Every message of a send that could not be delivered, each with its reason.
+    AbortedMessages(Vec<MessageErrorKind<I,M>>),
+    /// This many messages were given up on and moved to the dead-letter queue.
+    DeadLettered(usize)
 }
 
-/* impl<I,M> MessageErrorKind<I,M> {
+impl<I,M> MessageErrorKind<I,M> {
     fn as_str(&self) -> &'static str {
         match self {
-            MessageErrorKind::AbandonedMessages(_) => "Multiple Messages Abandoned",
-           MessageErrorKind::LockTimedOut(_) => "Abandoned Message Transmission: Lock on Write Permissions Timed Out"
+            MessageErrorKind::AbortedMessages(_) => "Multiple Messages Abandoned",
+            MessageErrorKind::LockTimedOut(_) => "Abandoned Message Transmission: Lock on Write Permissions Timed Out",
+            MessageErrorKind::UnknownRecipient(_) => "Abandoned Message Transmission: Recipient Unknown",
+            MessageErrorKind::RecipientDead(_) => "Abandoned Message Transmission: Recipient Removed",
+            MessageErrorKind::OutOfRange(_) => "Abandoned Message Transmission: Recipient Out of Range",
+            MessageErrorKind::DeadLettered(_) => "Abandoned Messages Moved to the Dead-Letter Queue"
         }
     }
-} */
+
+    /// The undelivered messages this error covers.
+    pub fn aborted_messages(&self) -> Vec<&AbortedMessage<I,M>> {
+        match self {
+            MessageErrorKind::LockTimedOut(aborted_message)
+            | MessageErrorKind::UnknownRecipient(aborted_message)
+            | MessageErrorKind::RecipientDead(aborted_message)
+            | MessageErrorKind::OutOfRange(aborted_message) => vec![aborted_message],
+            MessageErrorKind::AbortedMessages(error_kinds) => error_kinds
+                .iter()
+                .flat_map(|error_kind| error_kind.aborted_messages())
+                .collect(),
+            MessageErrorKind::DeadLettered(_) => Vec::new()
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct AbortedMessage<I,M> {
@@ -75,4 +111,24 @@ impl<I,M> AbortedMessage<I,M> {
     pub fn new(sender_id: I, recipient_id: I, message_contents: M) -> Self {
         Self {sender_id, recipient_id, message_contents}
     }
+
+    pub fn get_sender_id(&self) -> &I {
+        &self.sender_id
+    }
+
+    pub fn get_recipient_id(&self) -> &I {
+        &self.recipient_id
+    }
+
+    pub fn get_contents(&self) -> &M {
+        &self.message_contents
+    }
+
+    pub fn into_contents(self) -> M {
+        self.message_contents
+    }
+
+    pub fn into_parts(self) -> (I, I, M) {
+        (self.sender_id, self.recipient_id, self.message_contents)
+    }
 }
@@ -4,10 +4,12 @@ use crate::utils;
 
 use utils::Cell;
 
-use super::{Inbox, MessageFlush, MessageQueue};
+use super::{Inbox, MessageFlush, MessageQueue, Order, Envelope, ConversationId};
 
+/// Every message received since the last flush, handed out in the inbox's `Order`.
 pub struct AgentInbox<I,M> {
-    received_messages: collections::HashMap<I,M>
+    received_messages: collections::VecDeque<(I,M)>,
+    order: Order<M>
 }
 
 impl<I,M> AgentInbox<I,M> 
@@ -16,7 +18,77 @@ where
 {
     pub fn into_cell(self) -> Cell<Self> { rc::Rc::new(cell::RefCell::new(self)) }
 
-    pub fn get_messages<'a>(&'a self) -> &'a collections::HashMap<I, M> { &self.received_messages } 
+    pub fn with_order(mut self, order: Order<M>) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn set_order(&mut self, order: Order<M>) {
+        self.order = order
+    }
+
+    pub fn get_messages<'a>(&'a self) -> &'a collections::VecDeque<(I, M)> { &self.received_messages } 
+
+    /// The messages grouped by who sent them, each group in arrival order.
+    pub fn by_sender(&self) -> collections::HashMap<&I, Vec<&M>> {
+        let mut senders: collections::HashMap<&I, Vec<&M>> = collections::HashMap::new();
+        for (sender_id, message) in self.received_messages.iter() {
+            senders.entry(sender_id)
+                .or_insert_with(Vec::new)
+                .push(message)
+        }
+        senders
+    }
+
+    pub fn messages_from<'a>(&'a self, sender_id: &'a I) -> impl Iterator<Item=&'a M> + 'a {
+        self.received_messages
+            .iter()
+            .filter(move |(id, _)| id == sender_id)
+            .map(|(_, message)| message)
+    }
+
+    /// Removes and returns the messages satisfying `which` in arrival order, leaving the rest queued.
+    pub fn take_where<F>(&mut self, which: F) -> Vec<(I,M)>
+    where
+        F: Fn(&M) -> bool
+    {
+        let (taken, rest): (collections::VecDeque<_>, collections::VecDeque<_>) = self.received_messages
+            .drain(..)
+            .partition(|(_, message)| which(message));
+        self.received_messages = rest;
+        taken.into_iter().collect()
+    }
+}
+
+impl<I,M> AgentInbox<I,Envelope<I,M>> 
+where
+    I: Eq + hash::Hash
+{
+    /// The replies received in `conversation`, in arrival order; the request that opened it is 
+    /// not a reply and is left out.
+    pub fn replies_to(&self, conversation: ConversationId) -> Vec<&Envelope<I,M>> {
+        self.received_messages
+            .iter()
+            .map(|(_, envelope)| envelope)
+            .filter(|envelope| envelope.conversation == conversation && envelope.reply_to.is_some())
+            .collect()
+    }
+
+    /// Removes and returns the replies received in `conversation`, leaving the rest queued.
+    pub fn take_replies(&mut self, conversation: ConversationId) -> Vec<Envelope<I,M>> {
+        self.take_where(|envelope| envelope.conversation == conversation && envelope.reply_to.is_some())
+            .into_iter()
+            .map(|(_, envelope)| envelope)
+            .collect()
+    }
+
+    /// Removes and returns every message received in `conversation`, the opening request included.
+    pub fn take_conversation(&mut self, conversation: ConversationId) -> Vec<Envelope<I,M>> {
+        self.take_where(|envelope| envelope.conversation == conversation)
+            .into_iter()
+            .map(|(_, envelope)| envelope)
+            .collect()
+    }
 }
 
 impl<I,M> Inbox<I,M> for AgentInbox<I,M>
@@ -24,7 +96,7 @@ where
     I: Eq + hash::Hash,
 {
     fn read_msg<'a>(&'a self, id: &'a I) -> Option<&'a M> {
-        self.received_messages.get(id)
+        self.messages_from(id).next()
     }
 }
 
@@ -34,22 +106,30 @@ where
 {
     fn push(&mut self, sender_id: I, message: M) {
         self.received_messages
-            .insert(sender_id, message);
+            .push_back((sender_id, message));
     }
 
-    fn pop(&mut self) -> Option<(&I, &M)> {
+    fn pop(&mut self) -> Option<(I, M)> {
+        let position: usize = self.order.next(&self.received_messages)?;
+        self.received_messages.remove(position)
+    }
+
+    fn peek(&self) -> Option<(&I, &M)> {
+        let position: usize = self.order.next(&self.received_messages)?;
         self.received_messages
-            .iter()
-            .last()
+            .get(position)
+            .map(|(sender_id, message)| (sender_id, message))
     }   
 }
 
 impl<'a,I:'a,M:'a> MessageFlush<'a,I,M> for AgentInbox<I,M>
 {
-    type Drain = collections::hash_map::Drain<'a,I,M>;
+    type Drain = collections::vec_deque::Drain<'a,(I,M)>;
 
+    /// Drains every message in the same order `pop` would return them.
     fn flush_messages(&'a mut self) -> Self::Drain {
-        self.received_messages.drain()  
+        self.order.arrange(&mut self.received_messages);
+        self.received_messages.drain(..)  
     }
 }
 
@@ -58,7 +138,10 @@ where
     I: Eq + hash::Hash 
 {
     fn default() -> Self {
-        Self { received_messages: collections::HashMap::new() }
+        Self { 
+            received_messages: collections::VecDeque::new(),
+            order: Order::default()
+        }
     }
 }
 
@@ -2,7 +2,7 @@ use crate::utils;
 use utils::{Cell,sync};
 use sync::GreedyLock;
 
-use super::{Inbox, Outbox, MessageFlush, MessageQueue, error};
+use super::{Inbox, Outbox, MessageFlush, MessageQueue, Order, Recipients, Delivery, RetryPolicy, Latency, Range, Channel, InTransit, Envelope, MessageId, ConversationId, traffic, error};
 
 use std::{hash, fmt};
 
@@ -53,6 +53,8 @@ mod tests
     use std::collections;
 
     use crate::utils::sync::GreedyLock;
+    use crate::context;
+    use super::super::{Traffic, Counts};
 
     extern crate test;
 
@@ -84,6 +86,30 @@ mod tests
         Finish,
     }
 
+    impl super::super::Priority for TestMessage {
+        fn priority(&self) -> isize {
+            match self {
+                TestMessage::RequestSync => 0,
+                TestMessage::Acknowledge => 1,
+                TestMessage::Finish => 2
+            }
+        }
+    }
+
+    impl traffic::Measure for TestMessage {
+        fn label(&self) -> &'static str {
+            match self {
+                TestMessage::RequestSync => "RequestSync",
+                TestMessage::Acknowledge => "Acknowledge",
+                TestMessage::Finish => "Finish"
+            }
+        }
+
+        fn size(&self) -> Option<usize> {
+            Some(std::mem::size_of::<Self>())
+        }
+    }
+
     #[derive(Copy,Clone,Debug,PartialEq,Eq,Hash)]
     struct TestIdentity(usize); 
 
@@ -125,14 +151,13 @@ mod tests
         let mut test_inbox: inbox::AgentInbox<_,_> = inbox::AgentInbox::default();
         test_inbox.push(dummy_identity, dummy_message);
 
-        let mut expected_inbox: collections::HashMap<TestIdentity,TestMessage> = collections::HashMap::new();
+        let mut expected_inbox: collections::VecDeque<(TestIdentity,TestMessage)> = collections::VecDeque::new();
 
-        expected_inbox.insert(dummy_identity, dummy_message);
+        expected_inbox.push_back((dummy_identity, dummy_message));
 
         assert_eq!(test_inbox.get_messages(), &expected_inbox);
     }
 
-    #[ignore="Popping from Inbox returns a random message."]
     #[test]
     fn test_inbox_pop()
     {
@@ -150,12 +175,36 @@ mod tests
             } 
         }
 
+        assert_eq!(test_inbox.peek(), Some((&TestIdentity(0), &TestMessage::Acknowledge)));
+        assert_eq!(test_inbox.pop(), Some((TestIdentity(0), TestMessage::Acknowledge)));
+
+        test_inbox.set_order(Order::Lifo);
         let (test_identity,test_message): _ = test_inbox.pop().unwrap();
-        let expected_identity: _ = TestIdentity(2);
+        let expected_identity: _ = TestIdentity(population-1);
         let expected_message: _ = TestMessage::RequestSync;
 
-        assert_eq!(test_identity, &expected_identity);
-        assert_eq!(test_message, &expected_message);
+        assert_eq!(test_identity, expected_identity);
+        assert_eq!(test_message, expected_message);
+        assert_eq!(test_inbox.get_messages().len(), population-2);
+    }
+
+    #[test]
+    fn test_priority_pop()
+    {
+        let mut test_inbox: inbox::AgentInbox<_,_> = inbox::AgentInbox::default().with_order(Order::by_priority());
+        test_inbox.push(TestIdentity(0), TestMessage::RequestSync);
+        test_inbox.push(TestIdentity(1), TestMessage::Finish);
+        test_inbox.push(TestIdentity(2), TestMessage::Acknowledge);
+        test_inbox.push(TestIdentity(3), TestMessage::Finish);
+
+        assert_eq!(test_inbox.peek(), Some((&TestIdentity(1), &TestMessage::Finish)));
+        assert_eq!(test_inbox.pop(), Some((TestIdentity(1), TestMessage::Finish)));
+
+        let flushed: Vec<TestIdentity> = test_inbox.flush_messages()
+            .map(|(sender_id, _)| sender_id)
+            .collect();
+        assert_eq!(flushed, vec![TestIdentity(3), TestIdentity(2), TestIdentity(0)]);
+        assert_eq!(test_inbox.pop(), None);
     }
 
     #[test]
@@ -179,18 +228,517 @@ mod tests
         let addresses: _ = spawn_test_environment(2).inbox_addresses;
 
         let mut test_outbox: outbox::AgentOutbox<_,_> = outbox::AgentOutbox::new(TestIdentity(0), addresses);
-        let mut expected_outbox: collections::HashMap<TestIdentity,TestMessage> = collections::HashMap::new();
+        let mut expected_outbox: collections::VecDeque<(TestIdentity,TestMessage)> = collections::VecDeque::new();
 
         test_outbox.push(TestIdentity(1), TestMessage::RequestSync);
-        expected_outbox.insert(TestIdentity(1), TestMessage::RequestSync);
+        test_outbox.push(TestIdentity(1), TestMessage::Finish);
+        expected_outbox.push_back((TestIdentity(1), TestMessage::RequestSync));
+        expected_outbox.push_back((TestIdentity(1), TestMessage::Finish));
 
         assert_eq!(test_outbox.get_unsent_messages(), &expected_outbox);
     }
 
-    #[ignore="Popping from Outbox returns a random message."]
     #[test]
-    fn test_outbox_pop() {
-        unimplemented!()
+    fn test_inbox_keeps_every_message()
+    {
+        let mut test_inbox: inbox::AgentInbox<_,_> = inbox::AgentInbox::default();
+        test_inbox.push(TestIdentity(0), TestMessage::RequestSync);
+        test_inbox.push(TestIdentity(1), TestMessage::Acknowledge);
+        test_inbox.push(TestIdentity(0), TestMessage::Finish);
+
+        let senders: collections::HashMap<&TestIdentity, Vec<&TestMessage>> = test_inbox.by_sender();
+        assert_eq!(senders[&TestIdentity(0)], vec![&TestMessage::RequestSync, &TestMessage::Finish]);
+        assert_eq!(senders[&TestIdentity(1)], vec![&TestMessage::Acknowledge]);
+        assert_eq!(test_inbox.read_msg(&TestIdentity(0)), Some(&TestMessage::RequestSync));
+
+        let taken: Vec<(TestIdentity,TestMessage)> = test_inbox.take_where(|message| *message == TestMessage::Acknowledge);
+        assert_eq!(taken, vec![(TestIdentity(1), TestMessage::Acknowledge)]);
+
+        let flushed: Vec<(TestIdentity,TestMessage)> = test_inbox.flush_messages().collect();
+        assert_eq!(flushed, vec![
+            (TestIdentity(0), TestMessage::RequestSync),
+            (TestIdentity(0), TestMessage::Finish)
+        ]);
+    }
+
+    #[test]
+    fn test_outbox_pop() 
+    {
+        let addresses: _ = spawn_test_environment(3).inbox_addresses;
+
+        let mut test_outbox: outbox::AgentOutbox<_,_> = outbox::AgentOutbox::new(TestIdentity(0), addresses);
+        test_outbox.push(TestIdentity(1), TestMessage::RequestSync);
+        test_outbox.push(TestIdentity(2), TestMessage::Acknowledge);
+
+        assert_eq!(test_outbox.pop(), Some((TestIdentity(1), TestMessage::RequestSync)));
+        test_outbox.push(TestIdentity(1), TestMessage::Finish);
+
+        test_outbox.set_order(Order::Lifo);
+        assert_eq!(test_outbox.peek(), Some((&TestIdentity(1), &TestMessage::Finish)));
+        assert_eq!(test_outbox.pop(), Some((TestIdentity(1), TestMessage::Finish)));
+        assert_eq!(test_outbox.pop(), Some((TestIdentity(2), TestMessage::Acknowledge)));
+        assert_eq!(test_outbox.pop(), None);
+    }
+
+    #[test]
+    fn test_multicast()
+    {
+        let test_environment: TestEnvironment = spawn_test_environment(6);
+        {
+            let mut addresses: std::cell::RefMut<_> = test_environment.inbox_addresses.borrow_mut();
+            for round in 0..6 {
+                addresses.set_kind(TestIdentity(round), if round % 2 == 0 { "even" } else { "odd" });
+            }
+            addresses.join("sentries", TestIdentity(4));
+            addresses.join("sentries", TestIdentity(5));
+
+            // everyone stands in a row, one step apart
+            let mut grid: context::grid::Grid<TestIdentity> = context::grid::Grid::new(1, 10);
+            for round in 0..6 {
+                grid.replace(&context::grid::PointIndex::new(0, round), context::grid::PointState::Occupied(TestIdentity(round)));
+            }
+            let position: _ = context::GridPosition::new(context::grid::PointIndex::new(0, 0), grid.into_cell());
+            addresses.set_locator(TestIdentity(0), context::GridPosition::locator(position.into_cell(), |id: &TestIdentity| *id));
+        }
+
+        let sender: &TestAgent = &test_environment.agents[0];
+        let mut outbox: std::cell::RefMut<outbox::AgentOutbox<_,_>> = sender.message_module
+            .agent_outbox
+            .borrow_mut();
+
+        assert_eq!(outbox.multicast(Recipients::All, TestMessage::RequestSync), 5);
+        assert_eq!(outbox.multicast(Recipients::Kind("even".to_string()), TestMessage::Acknowledge), 2);
+        assert_eq!(outbox.multicast(Recipients::Group("sentries".to_string()), TestMessage::Finish), 2);
+        assert_eq!(outbox.multicast(Recipients::Within(2), TestMessage::Finish), 2);
+        assert_eq!(outbox.multicast(Recipients::Within(9), TestMessage::Finish), 5);
+        outbox.try_send().unwrap();
+
+        let received: _ = |id: usize| -> Vec<TestMessage> {
+            test_environment.agents[id].message_module
+                .agent_inbox
+                .borrow()
+                .messages_from(&TestIdentity(0))
+                .cloned()
+                .collect()
+        };
+        assert_eq!(received(0), vec![]);
+        assert_eq!(received(1), vec![TestMessage::RequestSync, TestMessage::Finish, TestMessage::Finish]);
+        assert_eq!(received(4), vec![TestMessage::RequestSync, TestMessage::Acknowledge, TestMessage::Finish, TestMessage::Finish]);
+    }
+
+    #[test]
+    fn test_undeliverable_messages_are_returned()
+    {
+        let test_environment: TestEnvironment = spawn_test_environment(3);
+        test_environment.inbox_addresses
+            .borrow_mut()
+            .remove(&TestIdentity(2));
+
+        let mut outbox: std::cell::RefMut<outbox::AgentOutbox<_,_>> = test_environment.agents[0]
+            .message_module
+            .agent_outbox
+            .borrow_mut();
+        outbox.push(TestIdentity(2), TestMessage::Finish);
+        outbox.push(TestIdentity(7), TestMessage::RequestSync);
+        outbox.push(TestIdentity(1), TestMessage::Acknowledge);
+
+        let send_error: error::MessageError<_,_> = outbox.try_send().unwrap_err();
+        match send_error.kind() {
+            error::MessageErrorKind::AbortedMessages(error_kinds) => match error_kinds.as_slice() {
+                [error::MessageErrorKind::RecipientDead(dead), error::MessageErrorKind::UnknownRecipient(unknown)] => {
+                    assert_eq!(dead.get_recipient_id(), &TestIdentity(2));
+                    assert_eq!(unknown.get_contents(), &TestMessage::RequestSync);
+                },
+                other => panic!("unexpected aborted messages {:?}", other)
+            },
+            other => panic!("unexpected error {:?}", other)
+        }
+        assert_eq!(send_error.kind().aborted_messages().len(), 2);
+
+        let delivered: Vec<TestMessage> = test_environment.agents[1]
+            .message_module
+            .agent_inbox
+            .borrow()
+            .messages_from(&TestIdentity(0))
+            .cloned()
+            .collect();
+        assert_eq!(delivered, vec![TestMessage::Acknowledge]);
+    }
+
+    #[test]
+    fn test_requeue_and_dead_letters()
+    {
+        let test_environment: TestEnvironment = spawn_test_environment(3);
+        test_environment.inbox_addresses
+            .borrow_mut()
+            .set_delivery(Delivery {
+                lock_timeout: std::time::Duration::from_millis(1),
+                retry: RetryPolicy { requeues: 1, backoff_ticks: 0 },
+                dead_letters: true
+            });
+
+        let mut outbox: std::cell::RefMut<outbox::AgentOutbox<_,_>> = test_environment.agents[0]
+            .message_module
+            .agent_outbox
+            .borrow_mut();
+        let inbox: _ = |id: usize| test_environment.agents[id].message_module.agent_inbox.clone();
+
+        // the recipient is busy for a whole send, so its message waits for the next one
+        let busy: _ = inbox(1);
+        let held: std::cell::RefMut<_> = busy.borrow_mut();
+        outbox.push(TestIdentity(1), TestMessage::RequestSync);
+        outbox.push(TestIdentity(2), TestMessage::RequestSync);
+        outbox.try_send().unwrap();
+        assert_eq!(outbox.requeued(), 1);
+        drop(held);
+
+        outbox.try_send().unwrap();
+        assert_eq!(outbox.requeued(), 0);
+        assert_eq!(inbox(1).borrow().get_messages().len(), 1);
+
+        // busy for two sends in a row, it is given up on, while an unknown recipient is given up on at once
+        let held: std::cell::RefMut<_> = busy.borrow_mut();
+        outbox.push(TestIdentity(1), TestMessage::Finish);
+        outbox.push(TestIdentity(9), TestMessage::Finish);
+        for _ in 0..2 {
+            match outbox.try_send().unwrap_err().kind() {
+                error::MessageErrorKind::DeadLettered(1) => { },
+                other => panic!("unexpected error {:?}", other)
+            }
+        }
+        drop(held);
+
+        let dead_letters: Vec<error::MessageErrorKind<_,_>> = test_environment.inbox_addresses
+            .borrow_mut()
+            .take_dead_letters();
+        match dead_letters.as_slice() {
+            [error::MessageErrorKind::UnknownRecipient(_), error::MessageErrorKind::LockTimedOut(timed_out)] => {
+                assert_eq!(timed_out.get_recipient_id(), &TestIdentity(1))
+            },
+            other => panic!("unexpected dead letters {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_requeue_backoff()
+    {
+        let test_environment: TestEnvironment = spawn_test_environment(2);
+        let addresses: Cell<outbox::AddressCollection<_,_>> = test_environment.inbox_addresses.clone();
+        addresses.borrow_mut().set_delivery(Delivery {
+            lock_timeout: std::time::Duration::from_millis(1),
+            retry: RetryPolicy { requeues: 3, backoff_ticks: 1 },
+            dead_letters: false
+        });
+        assert_eq!((0..4).map(|requeues| addresses.borrow().get_delivery().retry.backoff(requeues)).collect::<Vec<u64>>(), vec![1, 2, 4, 8]);
+
+        let mut outbox: std::cell::RefMut<outbox::AgentOutbox<_,_>> = test_environment.agents[0]
+            .message_module
+            .agent_outbox
+            .borrow_mut();
+        let inbox: Cell<inbox::AgentInbox<_,_>> = test_environment.agents[1].message_module.agent_inbox.clone();
+
+        // times out on the first send and again on the retry the next send makes
+        let held: std::cell::RefMut<_> = inbox.borrow_mut();
+        outbox.push(TestIdentity(1), TestMessage::RequestSync);
+        outbox.try_send().unwrap();
+        outbox.try_send().unwrap();
+        drop(held);
+
+        // so the second retry waits out two sends
+        outbox.try_send().unwrap();
+        assert_eq!((outbox.requeued(), inbox.borrow().get_messages().len()), (1, 0));
+        outbox.try_send().unwrap();
+        assert_eq!((outbox.requeued(), inbox.borrow().get_messages().len()), (0, 1));
+        drop(outbox);
+
+        // messages in transit back off in ticks the same way
+        inbox.borrow_mut().flush_messages().for_each(drop);
+        addresses.borrow_mut().set_latency(Latency::Fixed(1));
+        test_environment.agents[0].message_module.agent_outbox.borrow_mut().push(TestIdentity(1), TestMessage::Finish);
+        test_environment.agents[0].message_module.agent_outbox.borrow_mut().try_send().unwrap();
+
+        let held: std::cell::RefMut<_> = inbox.borrow_mut();
+        addresses.borrow_mut().advance().unwrap();
+        addresses.borrow_mut().advance().unwrap();
+        drop(held);
+
+        addresses.borrow_mut().advance().unwrap();
+        assert_eq!((addresses.borrow().in_transit(), inbox.borrow().get_messages().len()), (1, 0));
+        addresses.borrow_mut().advance().unwrap();
+        assert_eq!((addresses.borrow().in_transit(), inbox.borrow().get_messages().len()), (0, 1));
+    }
+
+    #[test]
+    fn test_delayed_delivery()
+    {
+        let test_environment: TestEnvironment = spawn_test_environment(4);
+        let addresses: Cell<outbox::AddressCollection<_,_>> = test_environment.inbox_addresses.clone();
+        let received: _ = |id: usize| -> Vec<TestMessage> {
+            test_environment.agents[id].message_module
+                .agent_inbox
+                .borrow()
+                .get_messages()
+                .iter()
+                .map(|(_, message)| *message)
+                .collect()
+        };
+        let send: _ = |recipient_id: usize, message: TestMessage| {
+            let mut outbox: std::cell::RefMut<outbox::AgentOutbox<_,_>> = test_environment.agents[0]
+                .message_module
+                .agent_outbox
+                .borrow_mut();
+            outbox.push(TestIdentity(recipient_id), message);
+            outbox.try_send().unwrap();
+        };
+
+        addresses.borrow_mut().set_latency(Latency::Fixed(2));
+        send(1, TestMessage::RequestSync);
+        addresses.borrow_mut().set_latency(Latency::uniform(1, 1));
+        send(1, TestMessage::Acknowledge);
+        assert_eq!(addresses.borrow().in_transit(), 2);
+
+        addresses.borrow_mut().advance().unwrap();
+        assert_eq!(received(1), vec![TestMessage::Acknowledge]);
+        addresses.borrow_mut().advance().unwrap();
+        assert_eq!(received(1), vec![TestMessage::Acknowledge, TestMessage::RequestSync]);
+
+        // the further away the recipient, the longer the message takes
+        addresses.borrow_mut().set_latency(Latency::by_distance(0, 1.0, |lhs: &TestIdentity, rhs: &TestIdentity| {
+            Some((lhs.0 as f64 - rhs.0 as f64).abs())
+        }));
+        send(3, TestMessage::Finish);
+        send(2, TestMessage::Finish);
+        addresses.borrow_mut().advance().unwrap();
+        assert_eq!((received(2).len(), received(3).len()), (0, 0));
+        addresses.borrow_mut().advance().unwrap();
+        assert_eq!((received(2).len(), received(3).len()), (1, 0));
+        addresses.borrow_mut().advance().unwrap();
+        assert_eq!((received(2).len(), received(3).len()), (1, 1));
+        assert_eq!(addresses.borrow().get_clock(), 5);
+    }
+
+    #[test]
+    fn test_uniform_latency_replays_under_a_seed()
+    {
+        let delays: _ = |seed: u64| -> Vec<u64> {
+            let mut addresses: outbox::AddressCollection<TestIdentity, TestMessage> = outbox::AddressCollection::default()
+                .with_latency(Latency::uniform(0, 1000))
+                .with_seed(seed);
+            (0..10)
+                .map(|id| addresses.delay(&TestIdentity(0), &TestIdentity(id)))
+                .collect()
+        };
+
+        assert_eq!(delays(46), delays(46));
+        assert_ne!(delays(46), delays(47));
+        assert!(delays(46).iter().all(|ticks| *ticks <= 1000));
+    }
+
+    #[test]
+    fn test_uniform_latency_reaches_the_largest_delay()
+    {
+        let mut addresses: outbox::AddressCollection<TestIdentity, TestMessage> = outbox::AddressCollection::default()
+            .with_latency(Latency::uniform(u64::MAX - 1, u64::MAX))
+            .with_seed(46);
+        let delays: Vec<u64> = (0..32)
+            .map(|id| addresses.delay(&TestIdentity(0), &TestIdentity(id)))
+            .collect();
+
+        assert!(delays.iter().all(|ticks| *ticks >= u64::MAX - 1));
+        assert!(delays.contains(&u64::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "min <= max")]
+    fn test_uniform_latency_rejects_inverted_bounds()
+    {
+        let _: Latency<TestIdentity> = Latency::uniform(3, 1);
+    }
+
+    #[test]
+    fn test_lossy_channels()
+    {
+        let test_environment: TestEnvironment = spawn_test_environment(4);
+        let addresses: Cell<outbox::AddressCollection<_,_>> = test_environment.inbox_addresses.clone();
+        let received: _ = |id: usize| -> Vec<TestMessage> {
+            test_environment.agents[id].message_module
+                .agent_inbox
+                .borrow_mut()
+                .flush_messages()
+                .map(|(_, message)| message)
+                .collect()
+        };
+        let send: _ = |messages: Vec<(usize, TestMessage)>| {
+            let mut outbox: std::cell::RefMut<outbox::AgentOutbox<_,_>> = test_environment.agents[0]
+                .message_module
+                .agent_outbox
+                .borrow_mut();
+            for (recipient_id, message) in messages {
+                outbox.push(TestIdentity(recipient_id), message);
+            }
+            outbox.try_send().unwrap();
+        };
+
+        addresses.borrow_mut().set_channel(Channel::new().with_drop(1.0));
+        addresses.borrow_mut().set_link(TestIdentity(0), TestIdentity(2), Channel::new().with_duplication(1.0));
+        addresses.borrow_mut().set_link(TestIdentity(0), TestIdentity(3), Channel::new()
+            .with_corruption(1.0, |_, _| TestMessage::Finish));
+        send(vec![(1, TestMessage::RequestSync), (2, TestMessage::RequestSync), (3, TestMessage::RequestSync)]);
+        assert!(received(1).is_empty());
+        assert_eq!(received(2), vec![TestMessage::RequestSync; 2]);
+        assert_eq!(received(3), vec![TestMessage::Finish]);
+
+        // the same seed shuffles the same way, and nothing is lost along the way
+        let shuffle: _ = |seed: u64| -> Vec<TestMessage> {
+            addresses.borrow_mut().set_seed(seed);
+            addresses.borrow_mut().set_link(TestIdentity(0), TestIdentity(1), Channel::new().with_reordering(0.5));
+            let messages: Vec<TestMessage> = (0..30)
+                .map(|n| [TestMessage::RequestSync, TestMessage::Acknowledge, TestMessage::Finish][n % 3])
+                .collect();
+            send(messages.iter().map(|message| (1, *message)).collect());
+            received(1)
+        };
+        let shuffled: Vec<TestMessage> = shuffle(5);
+        assert_eq!(shuffled, shuffle(5));
+        assert_eq!(shuffled.len(), 30);
+        assert_ne!(shuffled[..3], [TestMessage::RequestSync, TestMessage::Acknowledge, TestMessage::Finish][..]);
+        for message in [TestMessage::RequestSync, TestMessage::Acknowledge, TestMessage::Finish].iter() {
+            assert_eq!(shuffled.iter().filter(|other| *other == message).count(), 10);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "drop probability must lie in [0, 1], got NaN")]
+    fn test_channel_rejects_nan_probabilities()
+    {
+        let _: Channel<TestMessage> = Channel::new().with_drop(f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "reordering probability must lie in [0, 1], got 1.5")]
+    fn test_channel_rejects_out_of_range_probabilities()
+    {
+        let _: Channel<TestMessage> = Channel::new().with_reordering(1.5);
+    }
+
+    #[test]
+    fn test_range_limits_delivery()
+    {
+        let test_environment: TestEnvironment = spawn_test_environment(4);
+        let addresses: Cell<outbox::AddressCollection<_,_>> = test_environment.inbox_addresses.clone();
+        let send: _ = |recipient_id: usize| {
+            let mut outbox: std::cell::RefMut<outbox::AgentOutbox<_,_>> = test_environment.agents[0]
+                .message_module
+                .agent_outbox
+                .borrow_mut();
+            outbox.push(TestIdentity(recipient_id), TestMessage::RequestSync);
+            outbox.try_send()
+        };
+        let received: _ = |id: usize| test_environment.agents[id].message_module.agent_inbox.borrow().get_messages().len();
+
+        // the agents stand in a row, with a wall between agents 2 and 3
+        let range: _ = |limit: f64| Range::new(limit, |lhs: &TestIdentity, rhs: &TestIdentity| Some((lhs.0 as f64 - rhs.0 as f64).abs()));
+        addresses.borrow_mut().set_range(range(3.0)
+            .with_line_of_sight(|lhs: &TestIdentity, rhs: &TestIdentity| (lhs.0 <= 2) == (rhs.0 <= 2)));
+
+        assert!(send(2).is_ok());
+        let error: _ = send(3).unwrap_err();
+        match error.kind() {
+            error::MessageErrorKind::AbortedMessages(error_kinds) => {
+                assert!(matches!(error_kinds.as_slice(), [error::MessageErrorKind::OutOfRange(_)]))
+            },
+            other => panic!("expected the message to be out of range, not {:?}", other)
+        }
+        assert_eq!((received(2), received(3)), (1, 0));
+
+        addresses.borrow_mut().set_range(range(2.0).silently());
+        assert!(send(3).is_ok());
+        assert_eq!(received(3), 0);
+    }
+
+    #[test]
+    fn test_traffic_accounting()
+    {
+        let test_environment: TestEnvironment = spawn_test_environment(3);
+        let addresses: Cell<outbox::AddressCollection<_,_>> = test_environment.inbox_addresses.clone();
+        let measured: outbox::AddressCollection<_,_> = addresses.replace(Default::default()).measured();
+        addresses.replace(measured);
+        let send: _ = |sender_id: usize, messages: Vec<(usize, TestMessage)>| {
+            let mut outbox: std::cell::RefMut<outbox::AgentOutbox<_,_>> = test_environment.agents[sender_id]
+                .message_module
+                .agent_outbox
+                .borrow_mut();
+            for (recipient_id, message) in messages {
+                outbox.push(TestIdentity(recipient_id), message);
+            }
+            outbox.try_send()
+        };
+
+        send(0, vec![(1, TestMessage::RequestSync), (2, TestMessage::RequestSync)]).unwrap();
+        addresses.borrow_mut().advance().unwrap();
+        send(1, vec![(0, TestMessage::Acknowledge), (7, TestMessage::Acknowledge)]).unwrap_err();
+        addresses.borrow_mut().set_link(TestIdentity(2), TestIdentity(0), Channel::new().with_drop(1.0));
+        send(2, vec![(0, TestMessage::Acknowledge)]).unwrap();
+
+        let traffic: Traffic<TestIdentity> = addresses.borrow().traffic().clone();
+        let size: usize = std::mem::size_of::<TestMessage>();
+        assert_eq!(traffic.total(), Counts { 
+            sent: 5, 
+            delivered: 3, 
+            aborted: 1, 
+            dropped: 1, 
+            bytes_sent: 5 * size, 
+            bytes_delivered: 3 * size 
+        });
+        assert_eq!((traffic.tick(0).sent, traffic.tick(1).sent), (2, 3));
+        assert_eq!(traffic.sender(&TestIdentity(1)).aborted, 1);
+        assert_eq!(traffic.recipient(&TestIdentity(0)).delivered, 1);
+        assert_eq!(traffic.recipient(&TestIdentity(0)).dropped, 1);
+        assert_eq!(traffic.label("Acknowledge").sent, 3);
+        assert_eq!(traffic.label("RequestSync").delivered, 2);
+
+        assert_eq!(addresses.borrow_mut().take_traffic(), traffic);
+        assert_eq!(addresses.borrow().traffic().total(), Counts::default());
+    }
+
+    #[test]
+    fn test_conversations()
+    {
+        type Mailbox = MessageInterface<TestIdentity, Envelope<TestIdentity,TestMessage>>;
+
+        let addresses: Cell<outbox::AddressCollection<_,_>> = outbox::AddressCollection::default().into_cell();
+        let (alice, bob): (Mailbox, Mailbox) = (
+            MessageInterface::new(TestIdentity(0), addresses.clone()),
+            MessageInterface::new(TestIdentity(1), addresses.clone())
+        );
+        let send: _ = |mailbox: &Mailbox| mailbox.agent_outbox.borrow_mut().try_send().unwrap();
+
+        let sync: ConversationId = alice.agent_outbox.borrow_mut().request(TestIdentity(1), TestMessage::RequestSync);
+        let other: ConversationId = alice.agent_outbox.borrow_mut().request(TestIdentity(1), TestMessage::RequestSync);
+        send(&alice);
+        assert_ne!(sync, other);
+
+        assert!(bob.agent_inbox.borrow().replies_to(sync).is_empty());
+        assert!(bob.agent_inbox.borrow_mut().take_replies(sync).is_empty());
+        let request: Envelope<_,_> = bob.agent_inbox.borrow_mut().take_conversation(sync).remove(0);
+        assert_eq!((request.get_sender_id(), request.get_reply_to()), (&TestIdentity(0), None));
+        assert!(bob.agent_inbox.borrow().replies_to(other).is_empty());
+        bob.agent_outbox.borrow_mut().reply(&request, TestMessage::Acknowledge);
+        send(&bob);
+
+        addresses.borrow_mut().advance().unwrap();
+        let acknowledgement: Envelope<_,_> = alice.agent_inbox.borrow().replies_to(sync)[0].clone();
+        assert_eq!(acknowledgement.get_contents(), &TestMessage::Acknowledge);
+        assert_eq!(acknowledgement.get_reply_to(), Some(request.get_id()));
+        assert!(alice.agent_inbox.borrow().replies_to(other).is_empty());
+
+        let finish: MessageId = alice.agent_outbox.borrow_mut().reply(&acknowledgement, TestMessage::Finish);
+        send(&alice);
+        let replies: Vec<Envelope<_,_>> = bob.agent_inbox.borrow_mut().take_replies(sync);
+        assert_eq!(replies.len(), 1);
+        assert_eq!((replies[0].get_id(), replies[0].get_conversation()), (finish, sync));
+        assert_eq!((request.get_sent_at(), replies[0].get_sent_at()), (0, 1));
+        assert_eq!(bob.agent_inbox.borrow().get_messages().len(), 1);
     }
 
     #[test]
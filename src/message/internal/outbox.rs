@@ -1,5 +1,7 @@
 use std::{fmt,collections,hash};
 
+use rand::{SeedableRng, rngs};
+
 use crate::utils;
 
 use utils::{Cell,sync};
@@ -8,10 +10,14 @@ use sync::{GreedyBorrow, GreedyLock};
 
 use super::{inbox,error};
 
-use super::{MessageFlush,Outbox,MessageQueue};
+use super::{MessageFlush,Outbox,MessageQueue,Order,Recipients,Delivery,RetryPolicy,Latency,Range,Channel,InTransit,Envelope,MessageId,ConversationId,traffic};
 
 pub struct AgentOutbox<I,M> {
-    unsent_messages: collections::HashMap<I,M>,
+    unsent_messages: collections::VecDeque<(I,M)>,
+    // timed-out messages, with how often they have been carried over and the send they wait for
+    requeued_messages: collections::VecDeque<(I,M,usize,u64)>,
+    sends: u64,
+    order: Order<M>,
     pub(super) inbox_addresses: Cell<AddressCollection<I,M>>,
     pub(super) agent_identity: I
 }
@@ -22,19 +28,88 @@ where
 {
     pub fn new(id: I, addresses: Cell<AddressCollection<I,M>>) -> Self {
         Self { 
-            unsent_messages: collections::HashMap::new(),
+            unsent_messages: collections::VecDeque::new(),
+            requeued_messages: collections::VecDeque::new(),
+            sends: 0,
+            order: Order::default(),
             inbox_addresses: addresses,
             agent_identity: id
         }
     }
 
-    #[cfg(test)] pub fn get_unsent_messages<'a>(&'a mut self) -> &'a mut collections::HashMap<I, M> {
+    #[cfg(test)] pub fn get_unsent_messages<'a>(&'a mut self) -> &'a mut collections::VecDeque<(I, M)> {
         &mut self.unsent_messages
     }
 
     pub fn into_cell(self) -> Cell<Self> {
         std::rc::Rc::new(std::cell::RefCell::new(self))
     }
+
+    pub fn get_addresses(&self) -> Cell<AddressCollection<I,M>> {
+        self.inbox_addresses.clone()
+    }
+
+    /// How many timed-out messages are waiting to be retried on a later send.
+    pub fn requeued(&self) -> usize {
+        self.requeued_messages.len()
+    }
+
+    /// Queues a copy of `message` for everyone `recipients` resolves to, returning how many were queued.
+    pub fn multicast(&mut self, recipients: Recipients<I>, message: M) -> usize
+    where
+        I: Clone,
+        M: Clone
+    {
+        let recipient_ids: Vec<I> = self.inbox_addresses
+            .await_greedy_borrow()
+            .resolve(&self.agent_identity, &recipients);
+
+        for recipient_id in recipient_ids.iter() {
+            self.push(recipient_id.clone(), message.clone())
+        }
+        recipient_ids.len()
+    }
+
+    /// Sets the order messages are popped, flushed and sent in.
+    pub fn set_order(&mut self, order: Order<M>) {
+        self.order = order
+    }
+}
+
+impl<I,M> AgentOutbox<I,Envelope<I,M>> 
+where
+    I: Eq + hash::Hash + Clone
+{
+    /// Opens a conversation with `recipient_id`, returning its id to pick the replies out by.
+    pub fn request(&mut self, recipient_id: I, message: M) -> ConversationId {
+        let MessageId(id): MessageId = self.seal(recipient_id, None, None, message);
+        ConversationId(id)
+    }
+
+    /// Answers `envelope` within its conversation.
+    pub fn reply(&mut self, envelope: &Envelope<I,M>, message: M) -> MessageId {
+        self.seal(envelope.sender_id.clone(), Some(envelope.conversation), Some(envelope.id), message)
+    }
+
+    // queues the message in an envelope stamped with a fresh id and the current tick
+    fn seal(&mut self, recipient_id: I, conversation: Option<ConversationId>, reply_to: Option<MessageId>, contents: M) -> MessageId 
+    {
+        let (id, sent_at): (MessageId, u64) = {
+            let mut addresses: std::cell::RefMut<AddressCollection<_,_>> = self.inbox_addresses.await_greedy_lock();
+            (addresses.issue(), addresses.clock)
+        };
+        let envelope: Envelope<I,M> = Envelope {
+            id,
+            conversation: conversation.unwrap_or(ConversationId(id.0)),
+            reply_to,
+            sender_id: self.agent_identity.clone(),
+            recipient_id: recipient_id.clone(),
+            sent_at,
+            contents
+        };
+        self.push(recipient_id, envelope);
+        id
+    }
 }
 
 impl<I: Clone,M> Outbox<I,M> for AgentOutbox<I,M>
@@ -43,41 +118,99 @@ where
 {
     type Output = Result<(), error::MessageError<I,M>>;
 
+    /// Delivers every queued message it can under the collection's `Delivery` settings, carrying 
+    /// over timed-out messages the retry policy allows to the send its backoff points to and 
+    /// giving up on the rest. Messages first
+    /// pass through their `Channel` and must be within the collection's `Range`; those its 
+    /// `Latency` delays are handed to it to release on a later tick.
     fn try_send(&mut self) -> Self::Output 
     {
-        let addresses: &collections::HashMap<_,_> = &self.inbox_addresses
-            .await_greedy_borrow()
-            .addresses;
         let sender_identity: I = self.agent_identity.clone();
+        self.order.arrange(&mut self.unsent_messages);
+        self.sends += 1;
+
+        let transmitted: Vec<(I,M)> = self.inbox_addresses
+            .await_greedy_lock()
+            .transmit(&sender_identity, self.unsent_messages.drain(..));
+
+        let mut delayed: Vec<(u64,I,M)> = Vec::new();
+        let mut given_up: Vec<error::MessageErrorKind<I,M>> = Vec::new();
+        // what became of the other messages, counted once the collection can be written to
+        let mut accounted: Vec<(traffic::Event, I, traffic::Sample)> = Vec::new();
 
-        // TODO: set as environmental variable or configuration file.
-        let lock_timer: _ = std::time::Duration::from_millis(100);
-
-        let aborted_messages: Vec<error::AbortedMessage<_,_>> = self
-            .unsent_messages
-            .drain()
-            .filter_map(|(recipient_id,message)| 
-            {
-                match addresses[&recipient_id].await_greedy_lock_with_timeout(lock_timer) {
-                    Ok(mut rc) => {
-                        rc.push(sender_identity.clone(), message);
-                        None
+        {
+            let mut addresses: std::cell::RefMut<AddressCollection<_,_>> = self.inbox_addresses.await_greedy_lock();
+            let retry: RetryPolicy = addresses.delivery.retry;
+
+            // messages carried over from earlier sends go out first once their backoff is up, 
+            // and were already delayed
+            let sends: u64 = self.sends;
+            let (due, backing_off): (Vec<_>, Vec<_>) = self.requeued_messages
+                .drain(..)
+                .partition(|(_, _, _, due)| *due <= sends);
+            self.requeued_messages.extend(backing_off);
+            let mut pending: Vec<(I,M,usize)> = due
+                .into_iter()
+                .map(|(recipient_id, message, requeues, _)| (recipient_id, message, requeues))
+                .collect();
+            for (recipient_id, message) in transmitted.into_iter() {
+                match &addresses.range {
+                    Some(range) if !range.carries(&sender_identity, &recipient_id) => {
+                        if range.is_silent() {
+                            accounted.push((traffic::Event::Dropped, recipient_id, (addresses.sample)(&message)))
+                        } else {
+                            let aborted_message: _ = error::AbortedMessage::new(sender_identity.clone(), recipient_id, message);
+                            given_up.push(error::MessageErrorKind::OutOfRange(aborted_message))
+                        }
+                        continue
                     },
-                    Err(_) => {
-                        let aborted_message: _ = error::AbortedMessage::new(
-                            sender_identity.clone(), recipient_id, message
-                        );
-                        Some(aborted_message)
-                    }                    
-                }                
-            }).collect();
-
-        if aborted_messages.is_empty() {
-            Ok(())
-        } else {
-            let aborted_messages_error: _ = error::MessageErrorKind::AbortedMessages(aborted_messages);
-            Err(error::MessageError::from(aborted_messages_error))
+                    _ => { }
+                }
+                match addresses.delay(&sender_identity, &recipient_id) {
+                    0 => pending.push((recipient_id, message, 0)),
+                    delay => delayed.push((delay, recipient_id, message))
+                }
+            }
+
+            // a recipient busy now is tried again on a later send rather than waited on
+            let timed_out: Vec<(I,M,usize)> = pending
+                .into_iter()
+                .filter_map(|(recipient_id, message, requeues)| {
+                    let sample: traffic::Sample = (addresses.sample)(&message);
+                    match addresses.deliver(&sender_identity, recipient_id.clone(), message) {
+                        Ok(()) => {
+                            accounted.push((traffic::Event::Delivered, recipient_id, sample));
+                            None
+                        },
+                        Err(error::MessageErrorKind::LockTimedOut(aborted_message)) => {
+                            let (_, recipient_id, message): (I,I,M) = aborted_message.into_parts();
+                            Some((recipient_id, message, requeues))
+                        },
+                        Err(error_kind) => {
+                            given_up.push(error_kind);
+                            None
+                        }
+                    }
+                }).collect();
+
+            for (recipient_id, message, requeues) in timed_out.into_iter() {
+                if requeues < retry.requeues {
+                    self.requeued_messages.push_back((recipient_id, message, requeues + 1, sends + retry.backoff(requeues)))
+                } else {
+                    let aborted_message: _ = error::AbortedMessage::new(sender_identity.clone(), recipient_id, message);
+                    given_up.push(error::MessageErrorKind::LockTimedOut(aborted_message))
+                }
+            }
+        }
+
+        let mut addresses: std::cell::RefMut<AddressCollection<_,_>> = self.inbox_addresses.await_greedy_lock();
+        for (delay, recipient_id, message) in delayed.into_iter() {
+            addresses.dispatch(delay, sender_identity.clone(), recipient_id, message)
         }
+        for (event, recipient_id, sample) in accounted.into_iter() {
+            addresses.account(event, &sender_identity, &recipient_id, sample)
+        }
+        addresses.give_up(given_up)
     }     
 }
 
@@ -87,22 +220,29 @@ where
 {
     fn push(&mut self, recipient_id: I, message: M) {
         self.unsent_messages
-            .insert(recipient_id, message); 
+            .push_back((recipient_id, message)); 
+    }
+
+    fn pop(&mut self) -> Option<(I, M)> {
+        let position: usize = self.order.next(&self.unsent_messages)?;
+        self.unsent_messages.remove(position)
     }
 
-    fn pop(&mut self) -> Option<(&I, &M)> {
+    fn peek(&self) -> Option<(&I, &M)> {
+        let position: usize = self.order.next(&self.unsent_messages)?;
         self.unsent_messages
-            .iter()
-            .last()
+            .get(position)
+            .map(|(recipient_id, message)| (recipient_id, message))
     }   
 }
 
 impl<'a,I:'a,M:'a> MessageFlush<'a,I,M> for AgentOutbox<I,M>
 {
-    type Drain = collections::hash_map::Drain<'a,I,M>;
+    type Drain = collections::vec_deque::Drain<'a,(I,M)>;
 
     fn flush_messages(&'a mut self) -> Self::Drain {
-        self.unsent_messages.drain()
+        self.order.arrange(&mut self.unsent_messages);
+        self.unsent_messages.drain(..)
     }
 }
 
@@ -118,7 +258,24 @@ where
 }
 
 pub struct AddressCollection<I,M> {
-   addresses: collections::HashMap<I,Cell<inbox::AgentInbox<I,M>>>
+   addresses: collections::HashMap<I,Cell<inbox::AgentInbox<I,M>>>,
+   groups: collections::HashMap<String, collections::HashSet<I>>,
+   kinds: collections::HashMap<I, String>,
+   locators: collections::HashMap<I, Box<dyn Fn(usize) -> Vec<I>>>,
+   departed: collections::HashSet<I>,
+   delivery: Delivery,
+   dead_letters: Vec<error::MessageErrorKind<I,M>>,
+   latency: Latency<I>,
+   rng: rngs::StdRng,
+   range: Option<Range<I>>,
+   channel: Option<Channel<M>>,
+   links: collections::HashMap<I, collections::HashMap<I, Channel<M>>>,
+   clock: u64,
+   dispatched: u64,
+   issued: u64,
+   sample: fn(&M) -> traffic::Sample,
+   traffic: traffic::Traffic<I>,
+   in_transit: collections::BinaryHeap<InTransit<I,M>>
 }   
 
 impl<I,M> AddressCollection<I,M> 
@@ -132,6 +289,347 @@ where
     pub fn get_addresses(&mut self) -> &mut collections::HashMap<I,Cell<inbox::AgentInbox<I,M>>> {
         &mut self.addresses
     }
+
+    pub fn with_delivery(mut self, delivery: Delivery) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    pub fn set_delivery(&mut self, delivery: Delivery) {
+        self.delivery = delivery
+    }
+
+    pub fn get_delivery(&self) -> Delivery {
+        self.delivery
+    }
+
+    /// The messages outboxes have given up on, each with the reason, when `Delivery::dead_letters` is set.
+    pub fn dead_letters(&self) -> &[error::MessageErrorKind<I,M>] {
+        &self.dead_letters
+    }
+
+    pub fn take_dead_letters(&mut self) -> Vec<error::MessageErrorKind<I,M>> {
+        std::mem::take(&mut self.dead_letters)
+    }
+
+    /// Panics on a `Latency::Uniform` whose `min` is above its `max`.
+    pub fn with_latency(mut self, latency: Latency<I>) -> Self {
+        self.latency = latency.checked();
+        self
+    }
+
+    pub fn set_latency(&mut self, latency: Latency<I>) {
+        self.latency = latency.checked()
+    }
+
+    /// Seeds the generator random latencies and channel faults are drawn from, so that a run can be replayed exactly.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = rngs::StdRng::seed_from_u64(seed);
+        self
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = rngs::StdRng::seed_from_u64(seed)
+    }
+
+    pub(super) fn delay(&mut self, sender_id: &I, recipient_id: &I) -> u64 {
+        self.latency.delay(&mut self.rng, sender_id, recipient_id)
+    }
+
+    /// Limits how far messages carry; unlimited by default. Range is judged when a message is sent.
+    pub fn with_range(mut self, range: Range<I>) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    pub fn set_range(&mut self, range: Range<I>) {
+        self.range = Some(range)
+    }
+
+    /// Sets the channel every message passes through unless its link has one of its own.
+    pub fn with_channel(mut self, channel: Channel<M>) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    pub fn set_channel(&mut self, channel: Channel<M>) {
+        self.channel = Some(channel)
+    }
+
+    /// Sets the channel for messages from `sender_id` to `recipient_id` only.
+    pub fn set_link(&mut self, sender_id: I, recipient_id: I, channel: Channel<M>) {
+        self.links
+            .entry(sender_id)
+            .or_insert_with(collections::HashMap::new)
+            .insert(recipient_id, channel);
+    }
+
+    // takes the fields rather than `self` so the channel can draw from `rng` while it is borrowed
+    fn channel_for<'a>(
+        links: &'a collections::HashMap<I, collections::HashMap<I, Channel<M>>>,
+        channel: &'a Option<Channel<M>>,
+        sender_id: &I,
+        recipient_id: &I
+    ) -> Option<&'a Channel<M>> {
+        match links.get(sender_id).and_then(|link| link.get(recipient_id)) {
+            Some(channel) => Some(channel),
+            None => channel.as_ref()
+        }
+    }
+
+    // what comes out of the channels the messages are sent down: some lost, some doubled or 
+    // corrupted, and some held back behind those sent after them
+    fn transmit<T>(&mut self, sender_id: &I, messages: T) -> Vec<(I,M)>
+    where
+        I: Clone,
+        T: IntoIterator<Item = (I,M)>
+    {
+        let mut transmitted: Vec<(I,M)> = Vec::new();
+        for (recipient_id, message) in messages {
+            let sample: traffic::Sample = (self.sample)(&message);
+            self.account(traffic::Event::Sent, sender_id, &recipient_id, sample);
+            match Self::channel_for(&self.links, &self.channel, sender_id, &recipient_id) {
+                Some(channel) => {
+                    let copies: Vec<M> = channel.transmit(&mut self.rng, message);
+                    if copies.is_empty() {
+                        self.account(traffic::Event::Dropped, sender_id, &recipient_id, sample)
+                    }
+                    transmitted.extend(copies.into_iter().map(|copy| (recipient_id.clone(), copy)))
+                },
+                None => transmitted.push((recipient_id, message))
+            }
+        }
+
+        let count: usize = transmitted.len();
+        let mut places: Vec<usize> = Vec::with_capacity(count);
+        for (position, (recipient_id, _)) in transmitted.iter().enumerate() {
+            let rng: &mut rngs::StdRng = &mut self.rng;
+            let holdback: usize = Self::channel_for(&self.links, &self.channel, sender_id, recipient_id)
+                .map_or(0, |channel| channel.holdback(rng, count - position - 1));
+            places.push(position + holdback);
+        }
+
+        let mut placed: Vec<(usize,(I,M))> = places.into_iter().zip(transmitted).collect();
+        placed.sort_by_key(|(place, _)| *place);
+        placed.into_iter().map(|(_, message)| message).collect()
+    }
+
+    /// Counts messages under their own `Measure` label and size rather than all under their type.
+    pub fn measured(mut self) -> Self
+    where
+        M: traffic::Measure
+    {
+        self.sample = traffic::Sample::measured;
+        self
+    }
+
+    /// The traffic counted so far.
+    pub fn traffic(&self) -> &traffic::Traffic<I> {
+        &self.traffic
+    }
+
+    /// Hands over the traffic counted so far and starts counting afresh.
+    pub fn take_traffic(&mut self) -> traffic::Traffic<I> {
+        std::mem::take(&mut self.traffic)
+    }
+
+    fn account(&mut self, event: traffic::Event, sender_id: &I, recipient_id: &I, sample: traffic::Sample)
+    where
+        I: Clone
+    {
+        self.traffic.record(event, self.clock, sender_id, recipient_id, sample)
+    }
+
+    /// How many ticks `advance` has moved the clock on.
+    pub fn get_clock(&self) -> u64 {
+        self.clock
+    }
+
+    /// How many delayed messages have yet to arrive.
+    pub fn in_transit(&self) -> usize {
+        self.in_transit.len()
+    }
+
+    /// Moves the clock on a tick and releases every message due by then into its recipient's 
+    /// inbox, earliest first. A message whose inbox stays locked waits out the retry policy's 
+    /// backoff as often as it requeues.
+    pub fn advance(&mut self) -> Result<(), error::MessageError<I,M>>
+    where
+        I: Clone
+    {
+        self.clock += 1;
+        let mut given_up: Vec<error::MessageErrorKind<I,M>> = Vec::new();
+        let mut late: Vec<InTransit<I,M>> = Vec::new();
+
+        while self.in_transit.peek().map_or(false, |arrival| arrival.due <= self.clock) 
+        {
+            let arrival: InTransit<I,M> = match self.in_transit.pop() {
+                Some(arrival) => arrival,
+                None => break
+            };
+            let sample: traffic::Sample = (self.sample)(&arrival.message);
+            match self.deliver(&arrival.sender_id, arrival.recipient_id.clone(), arrival.message) {
+                Ok(()) => self.account(traffic::Event::Delivered, &arrival.sender_id, &arrival.recipient_id, sample),
+                Err(error::MessageErrorKind::LockTimedOut(aborted_message)) if arrival.requeues < self.delivery.retry.requeues => {
+                    let (sender_id, recipient_id, message): (I,I,M) = aborted_message.into_parts();
+                    late.push(InTransit { 
+                        due: self.clock.saturating_add(self.delivery.retry.backoff(arrival.requeues)), 
+                        sender_id, 
+                        recipient_id, 
+                        message, 
+                        requeues: arrival.requeues + 1, 
+                        ..arrival 
+                    })
+                },
+                Err(error_kind) => given_up.push(error_kind)
+            }
+        }
+        self.in_transit.extend(late);
+        self.give_up(given_up)
+    }
+
+    // a message id no envelope sealed through this collection has had
+    fn issue(&mut self) -> MessageId {
+        self.issued += 1;
+        MessageId(self.issued)
+    }
+
+    // sets a message on its way to arrive `delay` ticks from now
+    fn dispatch(&mut self, delay: u64, sender_id: I, recipient_id: I, message: M) {
+        self.dispatched += 1;
+        self.in_transit.push(InTransit {
+            due: self.clock.saturating_add(delay),
+            sequence: self.dispatched,
+            sender_id,
+            recipient_id,
+            message,
+            requeues: 0
+        })
+    }
+
+    // dead-letters the messages or hands them back, as the delivery settings say
+    fn give_up(&mut self, given_up: Vec<error::MessageErrorKind<I,M>>) -> Result<(), error::MessageError<I,M>> 
+    where
+        I: Clone
+    {
+        for error_kind in given_up.iter() {
+            for aborted_message in error_kind.aborted_messages() {
+                let sample: traffic::Sample = (self.sample)(aborted_message.get_contents());
+                self.traffic.record(traffic::Event::Aborted, self.clock, aborted_message.get_sender_id(), aborted_message.get_recipient_id(), sample)
+            }
+        }
+
+        if given_up.is_empty() {
+            Ok(())
+        } else if self.delivery.dead_letters {
+            let buried: usize = given_up.len();
+            self.dead_letters.extend(given_up);
+            Err(error::MessageError::from(error::MessageErrorKind::DeadLettered(buried)))
+        } else {
+            let aborted_messages_error: _ = error::MessageErrorKind::AbortedMessages(given_up);
+            Err(error::MessageError::from(aborted_messages_error))
+        }
+    }
+
+    // a single attempt at handing the message to the recipient's inbox
+    fn deliver(&self, sender_id: &I, recipient_id: I, message: M) -> Result<(), error::MessageErrorKind<I,M>>
+    where
+        I: Clone
+    {
+        let inbox: &Cell<inbox::AgentInbox<I,M>> = match self.addresses.get(&recipient_id) {
+            Some(inbox) => inbox,
+            None => {
+                let is_dead: bool = self.departed.contains(&recipient_id);
+                let aborted_message: _ = error::AbortedMessage::new(sender_id.clone(), recipient_id, message);
+                return Err(if is_dead {
+                    error::MessageErrorKind::RecipientDead(aborted_message)
+                } else {
+                    error::MessageErrorKind::UnknownRecipient(aborted_message)
+                })
+            }
+        };
+
+        match inbox.await_greedy_lock_with_timeout(self.delivery.lock_timeout) {
+            Ok(mut rc) => {
+                rc.push(sender_id.clone(), message);
+                Ok(())
+            },
+            Err(_) => {
+                let aborted_message: _ = error::AbortedMessage::new(sender_id.clone(), recipient_id, message);
+                Err(error::MessageErrorKind::LockTimedOut(aborted_message))
+            }
+        }
+    }
+
+    /// Unregisters an agent, so that messages still addressed to it abort as `RecipientDead`.
+    pub fn remove(&mut self, id: &I) -> Option<Cell<inbox::AgentInbox<I,M>>> 
+    where
+        I: Clone
+    {
+        let inbox: Cell<inbox::AgentInbox<I,M>> = self.addresses.remove(id)?;
+        for members in self.groups.values_mut() {
+            members.remove(id);
+        }
+        self.kinds.remove(id);
+        self.locators.remove(id);
+        self.departed.insert(id.clone());
+        Some(inbox)
+    }
+
+    pub fn join<S: Into<String>>(&mut self, group: S, id: I) {
+        self.groups
+            .entry(group.into())
+            .or_insert_with(collections::HashSet::new)
+            .insert(id);
+    }
+
+    pub fn leave(&mut self, group: &str, id: &I) {
+        if let Some(members) = self.groups.get_mut(group) {
+            members.remove(id);
+        }
+    }
+
+    pub fn set_kind<S: Into<String>>(&mut self, id: I, kind: S) {
+        self.kinds.insert(id, kind.into());
+    }
+
+    /// Registers how to find the agents within a radius of `id`, for `Recipients::Within`.
+    pub fn set_locator<F>(&mut self, id: I, locator: F) 
+    where
+        F: Fn(usize) -> Vec<I> + 'static
+    {
+        self.locators.insert(id, Box::new(locator));
+    }
+
+    /// Every registered agent `recipients` addresses on behalf of `sender_id`.
+    pub fn resolve(&self, sender_id: &I, recipients: &Recipients<I>) -> Vec<I>
+    where
+        I: Clone
+    {
+        let candidates: Vec<I> = match recipients {
+            Recipients::One(id) => vec![id.clone()],
+            Recipients::All => self.addresses.keys().cloned().collect(),
+            Recipients::Group(group) => self.groups
+                .get(group)
+                .map(|members| members.iter().cloned().collect())
+                .unwrap_or_default(),
+            Recipients::Kind(kind) => self.kinds
+                .iter()
+                .filter(|(_, other_kind)| *other_kind == kind)
+                .map(|(id, _)| id.clone())
+                .collect(),
+            Recipients::Within(radius) => self.locators
+                .get(sender_id)
+                .map(|locator| locator(*radius))
+                .unwrap_or_default()
+        };
+
+        // a single recipient is kept even when unregistered, so that delivery can report it
+        candidates.into_iter()
+            .filter(|id| id != sender_id)
+            .filter(|id| self.addresses.contains_key(id) || recipients == &Recipients::One(id.clone()))
+            .collect()
+    }
 }
 
 impl<I,M> Default for AddressCollection<I,M> 
@@ -139,7 +637,26 @@ where
     I: Eq + hash::Hash
 {
     fn default() -> Self {
-        Self { addresses: collections::HashMap::new() }
+        Self { 
+            addresses: collections::HashMap::new(),
+            groups: collections::HashMap::new(),
+            kinds: collections::HashMap::new(),
+            locators: collections::HashMap::new(),
+            departed: collections::HashSet::new(),
+            delivery: Delivery::default(),
+            dead_letters: Vec::new(),
+            latency: Latency::default(),
+            rng: rngs::StdRng::seed_from_u64(0),
+            range: None,
+            channel: None,
+            links: collections::HashMap::new(),
+            clock: 0,
+            dispatched: 0,
+            issued: 0,
+            sample: traffic::Sample::unmeasured,
+            traffic: traffic::Traffic::default(),
+            in_transit: collections::BinaryHeap::new()
+        }
     }
 } 
 
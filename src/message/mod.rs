@@ -3,8 +3,18 @@ mod internal;
 // mod external;
 
 mod error;
+mod delivery;
+mod channel;
+mod traffic;
+mod envelope;
 
 pub use internal::{MessageInterface, AddressCollection};
+pub use delivery::{Delivery, RetryPolicy, Latency, Range};
+pub use channel::Channel;
+pub use traffic::{Measure, Traffic, Counts};
+pub use envelope::{Envelope, MessageId, ConversationId};
+
+use delivery::InTransit;
 
 pub trait Outbox<ID,MSG> 
 {
@@ -29,5 +39,98 @@ pub trait MessageQueue<ID,MSG>
 {
     fn push(&mut self, id: ID, msg: MSG);
 
-    fn pop(&mut self) -> Option<(&ID, &MSG)>;
+    /// Removes and returns the next message in the queue's `Order`.
+    fn pop(&mut self) -> Option<(ID, MSG)>;
+
+    /// The message `pop` would return next, left in the queue.
+    fn peek(&self) -> Option<(&ID, &MSG)>;
+}
+
+/// Who a multicast message is addressed to; the sender never receives its own copy.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Recipients<ID> {
+    One(ID),
+    /// Every registered agent.
+    All,
+    /// The members of a group joined through `AddressCollection::join`.
+    Group(String),
+    /// The agents registered with this kind through `AddressCollection::set_kind`.
+    Kind(String),
+    /// The agents within this radius of the sender, as reported by the sender's locator.
+    Within(usize)
+}
+
+/// A key messages provide for `Order::Priority`, higher keys going first.
+pub trait Priority 
+{
+    fn priority(&self) -> isize;
+}
+
+/// The order a mailbox hands its messages out in.
+#[derive(Default)]
+pub enum Order<MSG> {
+    /// Oldest first.
+    #[default]
+    Fifo,
+    /// Newest first.
+    Lifo,
+    /// Highest key first, oldest first among equal keys.
+    Priority(fn(&MSG) -> isize)
+}
+
+impl<MSG: Priority> Order<MSG> {
+    pub fn by_priority() -> Self {
+        Order::Priority(MSG::priority)
+    }
+}
+
+impl<MSG> Order<MSG> 
+{
+    // the position of the message that goes next
+    fn next<ID>(&self, messages: &std::collections::VecDeque<(ID,MSG)>) -> Option<usize> {
+        match self {
+            Order::Fifo if !messages.is_empty() => Some(0),
+            Order::Lifo => messages.len().checked_sub(1),
+            Order::Priority(key) => messages
+                .iter()
+                .enumerate()
+                .max_by_key(|(position, (_, msg))| (key(msg), std::cmp::Reverse(*position)))
+                .map(|(position, _)| position),
+            _ => None
+        }
+    }
+
+    // rearranges the messages so that they are already in the order they go out in
+    fn arrange<ID>(&self, messages: &mut std::collections::VecDeque<(ID,MSG)>) {
+        match self {
+            Order::Fifo => { },
+            Order::Lifo => {
+                let reversed: Vec<(ID,MSG)> = messages.drain(..).rev().collect();
+                messages.extend(reversed)
+            },
+            Order::Priority(key) => {
+                let mut sorted: Vec<(ID,MSG)> = messages.drain(..).collect();
+                sorted.sort_by_key(|(_, msg)| std::cmp::Reverse(key(msg)));
+                messages.extend(sorted)
+            }
+        }
+    }
+}
+
+impl<MSG> Clone for Order<MSG> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<MSG> Copy for Order<MSG> { }
+
+impl<MSG> std::fmt::Debug for Order<MSG> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Order::Fifo => write!(f, "Fifo"),
+            Order::Lifo => write!(f, "Lifo"),
+            Order::Priority(_) => write!(f, "Priority")
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,147 @@
+use std::{collections, hash};
+
+/// What traffic accounting needs to know about a message.
+pub trait Measure
+{
+    /// The label the message is counted under, such as its variant.
+    fn label(&self) -> &'static str;
+
+    /// How many bytes the message would take on the wire, if that is known.
+    fn size(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Message counts for one slice of the traffic.
+#[derive(Debug,Clone,Copy,Default,PartialEq)]
+pub struct Counts {
+    pub sent: usize,
+    pub delivered: usize,
+    /// Given up on, with an error or into the dead-letter queue.
+    pub aborted: usize,
+    /// Lost on the way without a word, by a `Channel` or a silent `Range`.
+    pub dropped: usize,
+    pub bytes_sent: usize,
+    pub bytes_delivered: usize
+}
+
+impl Counts {
+    fn count(&mut self, event: Event, sample: Sample) {
+        let bytes: usize = sample.size.unwrap_or(0);
+        match event {
+            Event::Sent => {
+                self.sent += 1;
+                self.bytes_sent += bytes
+            },
+            Event::Delivered => {
+                self.delivered += 1;
+                self.bytes_delivered += bytes
+            },
+            Event::Aborted => self.aborted += 1,
+            Event::Dropped => self.dropped += 1
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub(super) enum Event {
+    Sent,
+    Delivered,
+    Aborted,
+    Dropped
+}
+
+/// The label and size a message is counted with.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub(super) struct Sample {
+    label: &'static str,
+    size: Option<usize>
+}
+
+impl Sample {
+    pub(super) fn measured<M: Measure>(message: &M) -> Self {
+        Self { label: message.label(), size: message.size() }
+    }
+
+    // messages that cannot measure themselves are all counted under their type
+    pub(super) fn unmeasured<M>(_: &M) -> Self {
+        Self { label: std::any::type_name::<M>(), size: None }
+    }
+}
+
+/// Everything an `AddressCollection` has counted, broken down by tick, sender, recipient and label.
+#[derive(Debug,Clone)]
+pub struct Traffic<I> {
+    total: Counts,
+    ticks: collections::BTreeMap<u64, Counts>,
+    senders: collections::HashMap<I, Counts>,
+    recipients: collections::HashMap<I, Counts>,
+    labels: collections::HashMap<&'static str, Counts>
+}
+
+impl<I> Traffic<I>
+where
+    I: Eq + hash::Hash
+{
+    pub fn total(&self) -> Counts {
+        self.total
+    }
+
+    /// The traffic of the tick the collection's clock read, as moved on by `advance`.
+    pub fn tick(&self, tick: u64) -> Counts {
+        self.ticks.get(&tick).copied().unwrap_or_default()
+    }
+
+    pub fn sender(&self, sender_id: &I) -> Counts {
+        self.senders.get(sender_id).copied().unwrap_or_default()
+    }
+
+    pub fn recipient(&self, recipient_id: &I) -> Counts {
+        self.recipients.get(recipient_id).copied().unwrap_or_default()
+    }
+
+    pub fn label(&self, label: &str) -> Counts {
+        self.labels.get(label).copied().unwrap_or_default()
+    }
+
+    /// Every label counted so far, with its counts.
+    pub fn labels(&self) -> &collections::HashMap<&'static str, Counts> {
+        &self.labels
+    }
+
+    pub(super) fn record(&mut self, event: Event, tick: u64, sender_id: &I, recipient_id: &I, sample: Sample)
+    where
+        I: Clone
+    {
+        self.total.count(event, sample);
+        self.ticks.entry(tick).or_default().count(event, sample);
+        self.senders.entry(sender_id.clone()).or_default().count(event, sample);
+        self.recipients.entry(recipient_id.clone()).or_default().count(event, sample);
+        self.labels.entry(sample.label).or_default().count(event, sample);
+    }
+}
+
+impl<I> PartialEq for Traffic<I>
+where
+    I: Eq + hash::Hash
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.total == other.total
+            && self.ticks == other.ticks
+            && self.senders == other.senders
+            && self.recipients == other.recipients
+            && self.labels == other.labels
+    }
+}
+
+impl<I> Default for Traffic<I> {
+    fn default() -> Self {
+        Self {
+            total: Counts::default(),
+            ticks: collections::BTreeMap::new(),
+            senders: collections::HashMap::new(),
+            recipients: collections::HashMap::new(),
+            labels: collections::HashMap::new()
+        }
+    }
+}
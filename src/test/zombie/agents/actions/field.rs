@@ -0,0 +1,58 @@
+use crate::test::zombie::agents;
+use agents::package;
+
+use crate::context::grid;
+use grid::FieldValue;
+
+use log::*;
+
+use std::ops::AddAssign;
+
+pub const SCENT: &str = "scent";
+pub const FOOD: &str = "food";
+pub const HUNT: &str = "hunt";
+
+pub struct Scent<'a> {
+    package: &'a package::Package
+}
+
+impl<'a> Scent<'a> {
+    pub fn new(package: &'a package::Package) -> Self {
+        Self { package }
+    }
+
+    pub fn leave_trail(self) {
+        let tile: _ = self.package.as_tile();
+        let current: f64 = tile.read_field(SCENT)
+            .and_then(|value| value.as_scalar())
+            .unwrap_or(0.0);
+        tile.write_field(SCENT, FieldValue::Scalar(current + 1.0));
+    }
+}
+
+pub struct Forage<'a> {
+    package: &'a package::Package
+}
+
+impl<'a> Forage<'a> {
+    pub fn new(package: &'a package::Package) -> Self {
+        Self { package }
+    }
+
+    pub fn eat(self) {
+        let tile: _ = self.package.as_tile();
+        let food: f64 = tile.read_field(FOOD)
+            .and_then(|value| value.as_scalar())
+            .unwrap_or(0.0);
+
+        if food >= 0.5 {
+            tile.write_field(FOOD, FieldValue::Scalar(0.0));
+            self.package
+                .as_statistics()
+                .health
+                .borrow_mut()
+                .add_assign(1);
+            info!("{} foraged at {:?}.", self.package.get_id(), tile)
+        }
+    }
+}
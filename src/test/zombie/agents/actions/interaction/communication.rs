@@ -42,29 +42,14 @@ impl<'a> Communication<'a> {
         F: Fn(&package::Package,&package::Package) -> bool
     {
         let agent: _ = &self.package;
-        let reports: Vec<grid::PointIndex> = agent.as_tile()
+        let own_idx: grid::PointIndex = agent.as_tile().get_idx();
+        let reports: Vec<grid::PointIndex> = agent.as_grid()
             .borrow()
-            .get_connections()
-            .expect("no connections on tile")
-            .iter()
-            .map(|connection| {
-                connection.borrow()
-                    .get_connections()
-                    .expect("no connections on tile")
-                    .iter()
-                    .filter_map(|tile| {
-                        let tile_idx: grid::PointIndex = tile.borrow().get_idx();
-                        if agent.as_tile().borrow().get_idx() == tile_idx {
-                            None
-                        } else if let grid::PointState::Occupied(other) = tile.borrow().state() {
-                            if trigger(agent, other) {
-                                Some(tile_idx)
-                            } else { 
-                                None
-                            }
-                        } else { None }
-                    }).collect::<Vec<_>>()
-            }).flatten().collect();
+            .within_radius(&own_idx, 2)
+            .into_iter()
+            .filter(|(tile_idx, other)| tile_idx != &own_idx && trigger(agent, other))
+            .map(|(tile_idx, _)| tile_idx)
+            .collect();
 
         if !reports.is_empty() {
             let report_inner: _ = agents::Report::new(report_kind, reports);
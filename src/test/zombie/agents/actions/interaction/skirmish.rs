@@ -55,13 +55,15 @@ impl<'a> Skirmish<'a> {
             .borrow()
             .abs();
 
-        for tile in rhs.as_tile()
-            .borrow()
-            .get_connections()
-            .expect("expected connections on tile")
+        let own_idx: grid::PointIndex = rhs.as_tile().get_idx();
+        let oracle: _ = rhs.as_grid();
+        let active_oracle: _ = oracle.borrow();
+
+        for tile in active_oracle
+            .get_connections(&own_idx)
             .iter()
         {
-            if let grid::PointState::Occupied(other) = tile.borrow().state() {
+            for other in active_oracle.state(tile).occupants() {
                 if other.as_kind().is(lhs.as_kind()) {
                     rhs_strength += other.as_statistics().strength.borrow().div(2);
                 }
@@ -1,3 +1,4 @@
 pub mod tile;
 pub mod movement;
 pub mod interaction;
+pub mod field;
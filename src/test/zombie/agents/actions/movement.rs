@@ -19,18 +19,16 @@ impl<'a> Movement<'a> {
 
         info!("{} is moving onto {:?} from {:?}", self.agent.get_id(),self.new, self.agent.get_package().as_tile());
 
-        if self.new
-            .borrow()
-            .state()
-            .is_empty()
-        {         
-            let package: &package::Package = self.agent.get_package();
-            package.as_tile()
-                .borrow_mut()
-                .move_inner_into(&mut self.new.borrow_mut());
+        let package: &package::Package = self.agent.get_package();
+        let id: usize = package.get_id();
+
+        let moved: bool = package.as_tile()
+            .transfer_where(&self.new, |other| other.get_id() == id);
+
+        if moved {
             package.swap_stored_position_with(self.new);
         } else {
-            unimplemented!()
+            info!("{} could not move onto {:?}: the point is full.", id, self.new)
         }
     }
 }
\ No newline at end of file
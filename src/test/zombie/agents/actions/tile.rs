@@ -1,8 +1,8 @@
 use crate::test::zombie::agents;
-use agents::{GetPackage, package};
+use agents::{package, actions};
 
 use crate::{context, message, utils};
-use context::grid;
+use context::{grid, Space};
 use utils::Cell;
 use message::MessageFlush;
 
@@ -22,14 +22,24 @@ impl<'a> Selection<'a> {
         let agent: _ = self.package;
         let own_kind: _ = agent.as_kind();
 
-        let tile: Option<Cell<grid::Point<_>>> = agent.as_tile()
-            .borrow()
-            .get_connections()
-            .unwrap()
+        if let Some(tile) = self.hunt().or_else(|| self.pursue()) {
+            self.log(&Some(tile.clone()));
+            let grid: _ = self.package.as_grid();
+            return Some(context::GridPosition::new(tile, grid))
+        }
+
+        let own_idx: grid::PointIndex = agent.as_tile().get_idx();
+        let reports: Vec<agents::Report> = self.take_reports();
+        let oracle: _ = self.package.as_grid();
+        let active_oracle: _ = oracle.borrow();
+
+        let tile: Option<grid::PointIndex> = active_oracle
+            .get_connections(&own_idx)
             .iter()
+            .filter(|idx| !active_oracle.state(idx).is_blocked())
             .max_by(|x,y| {
-                let evaluated_x: isize = Evaluation::new(x,own_kind, &self.package).evaluate_tile();
-                let evaluated_y: isize = Evaluation::new(y,own_kind, &self.package).evaluate_tile();
+                let evaluated_x: isize = Evaluation::new(x,own_kind, &self.package, &reports).evaluate_tile();
+                let evaluated_y: isize = Evaluation::new(y,own_kind, &self.package, &reports).evaluate_tile();
                 evaluated_x.cmp(&evaluated_y)
             }).cloned();
 
@@ -41,40 +51,113 @@ impl<'a> Selection<'a> {
         })
     }
 
-    fn log(&self, tile: &Option<Cell<grid::Point<package::Package>>>) {
+    // zombies follow the shared flow field toward the nearest human
+    fn hunt(&self) -> Option<grid::PointIndex> 
+    {
+        let agent: _ = self.package;
+        if !agent.as_kind().is(&agents::Kind::Zombie) {
+            return None
+        }
+
+        let own_idx: grid::PointIndex = agent.as_tile().get_idx();
+        let oracle: _ = agent.as_grid();
+        let active_oracle: _ = oracle.borrow();
+
+        active_oracle.flow_step(actions::field::HUNT, &own_idx)
+    }
+
+    // humans who feel they outnumber the zombies head for the nearest reported enemy
+    fn pursue(&self) -> Option<grid::PointIndex> 
+    {
+        let agent: _ = self.package;
+        let observer: _ = agent.as_observer().borrow();
+        if !agent.as_kind().is(&agents::Kind::Human) || observer.human_count <= observer.zombie_count/4 {
+            return None
+        }
+
+        let own_idx: grid::PointIndex = agent.as_tile().get_idx();
+        let oracle: _ = agent.as_grid();
+        let active_oracle: _ = oracle.borrow();
+
+        let target: grid::PointIndex = agent.as_messenger()
+            .agent_inbox
+            .borrow()
+            .get_messages()
+            .iter()
+            .filter_map(|(_, msg)| match msg {
+                agents::Message::Report(report) if report.is(&agents::ReportKind::Enemies) => Some(report.as_inner().clone()),
+                _ => None
+            })
+            .flatten()
+            .min_by_key(|idx| active_oracle.distance_between(&own_idx, idx))?;
+
+        let obstacles: grid::Obstacles = grid::Obstacles { occupied: true, claimed: true };
+        let path: Vec<grid::PointIndex> = active_oracle.astar(&own_idx, &target, None, grid::Heuristic::Toroidal, obstacles)?;
+        let next: grid::PointIndex = path.get(1)?.clone();
+
+        // the enemy reports have been acted upon, so they are not weighed again next turn
+        agent.as_messenger()
+            .agent_inbox
+            .borrow_mut()
+            .take_where(|msg| matches!(msg, agents::Message::Report(report) if report.is(&agents::ReportKind::Enemies)));
+
+        Some(next)
+    }
+
+    // every tile is weighed against the same reports, which are then spent
+    fn take_reports(&self) -> Vec<agents::Report> {
+        self.package
+            .as_messenger()
+            .agent_inbox
+            .borrow_mut()
+            .flush_messages()
+            .map(|(_, agents::Message::Report(report))| report)
+            .collect()
+    }
+
+    fn log(&self, tile: &Option<grid::PointIndex>) {
         info!("{} selected {:?} for their move.", self.package.get_id(), tile)
     }
 }
 
 struct Evaluation<'a> {
-    target: &'a Cell<grid::Point<package::Package>>,
+    target: &'a grid::PointIndex,
     kind: &'a agents::Kind,
     score: isize,
-    agent: &'a package::Package
+    agent: &'a package::Package,
+    reports: &'a [agents::Report]
 }
 
 impl<'a> Evaluation<'a> {
     pub fn new(
-        target: &'a Cell<grid::Point<package::Package>>, 
+        target: &'a grid::PointIndex, 
         kind: &'a agents::Kind,
-        agent: &'a package::Package
+        agent: &'a package::Package,
+        reports: &'a [agents::Report]
     ) -> Self {
         Self { 
             target,
             kind,
             agent,
+            reports,
             score: 0
         }
     }
 
     pub fn evaluate_tile(self) -> isize {
         self.kind()
+            .scent()
+            .terrain()
             .proximity()
             .score
     }
 
     fn kind(mut self) -> Self {
-        if let grid::PointState::Occupied(occupier) = self.target.borrow().state() {
+        let oracle: Cell<grid::Grid<_>> = self.agent.as_grid();
+        let active_oracle: _ = oracle.borrow();
+
+        // every occupant of a crowded point weighs in
+        for occupier in active_oracle.state(self.target).occupants() {
             if occupier.as_kind().is(self.kind) {
                 self.score += rand::thread_rng().gen_range(10,15)
             } else if self.kind.is(&agents::Kind::Human) {
@@ -87,114 +170,73 @@ impl<'a> Evaluation<'a> {
             } else {
                 self.score += 100
             }
-        };
+        }
         self
     }
 
-    fn proximity(mut self) -> Self {
-        let oracle: utils::Cell<context::grid::Grid<_>> = self.agent.as_grid();
-        let target: _ = self.target.borrow();
-        let agent_tile: _ = self.agent.as_tile();
-        let distance: _ = oracle.borrow()
-            .toroidal_distance_between(&target, &agent_tile.borrow());
-
-        for (_, msg) in self.agent
-            .get_package()
-            .as_messenger()
-            .agent_inbox
-            .borrow_mut()
-            .flush_messages()
-        {
-            if let agents::Message::Report(report) = msg {
-                self = DistanceEvaluation::new(report, distance, self).sort();
-            }
+    // zombies are drawn along the trails humans leave behind
+    fn scent(mut self) -> Self {
+        if self.kind.is(&agents::Kind::Zombie) {
+            let scent: f64 = self.agent
+                .as_grid()
+                .borrow()
+                .read_field(actions::field::SCENT, self.target)
+                .and_then(|value| value.as_scalar())
+                .unwrap_or(0.0);
+            self.score += (scent * 10.0) as isize
         }
         self
-    } 
-
-}
-
-struct DistanceEvaluation<'a> {
-    report: agents::Report,
-    benchmark_distance: usize,
-    evaluation: Evaluation<'a>
-}
-
-impl<'a> DistanceEvaluation<'a> {
-    fn new(report: agents::Report, benchmark_distance: usize, evaluation: Evaluation<'a>) -> Self {
-        Self {
-            report, 
-            benchmark_distance, 
-            evaluation
-        }
     }
 
-    fn sort(self) -> Evaluation<'a> {
-        if self.report.is(&agents::ReportKind::Allies) {
-            self.ally().evaluation
-        } else {
-            self.enemy().evaluation
-        }
+    // rough ground is avoided unless something else makes it worth crossing
+    fn terrain(mut self) -> Self {
+        let cost: f64 = self.agent
+            .as_grid()
+            .borrow()
+            .movement_cost(self.target);
+        self.score -= ((cost - 1.0) * 10.0) as isize;
+        self
     }
 
-    fn enemy(self) -> Self {
-        let agent: &package::Package = self.evaluation.agent;
-        if agent.as_kind().is(&agents::Kind::Human) {
-            let observer: _ = agent.as_observer();
-            if observer.borrow().human_count > observer.borrow().zombie_count/4 {
-                self.report_modifier(
-                    [std::ops::AddAssign::add_assign,std::ops::AddAssign::add_assign],
-                    [30, 10]
-                )      
-            } else {
-                self.report_modifier(
-                    [std::ops::SubAssign::sub_assign,std::ops::SubAssign::sub_assign],
-                    [20, 10]
-                )   
+    // zombies close in along the shared flow field, humans on what their reports reveal
+    fn proximity(mut self) -> Self {
+        let oracle: utils::Cell<context::grid::Grid<_>> = self.agent.as_grid();
+        let active_oracle: _ = oracle.borrow();
+        let agent_idx: grid::PointIndex = self.agent.as_tile().get_idx();
+
+        if self.kind.is(&agents::Kind::Zombie) {
+            if let Some(flow) = active_oracle.get_flow(actions::field::HUNT) {
+                let distances: &grid::DistanceMap = flow.as_distances();
+                self.score += match (distances.get(self.target), distances.get(&agent_idx)) {
+                    (Some(0), _) => 30,
+                    (Some(there), Some(here)) if there < here => 10,
+                    _ => 0
+                };
             }
-        } else {
-            self.report_modifier(
-                [std::ops::AddAssign::add_assign,std::ops::AddAssign::add_assign],
-                [30, 10]
-            )      
+            return self
         }
-    }
-
-    fn ally(self) -> Self {       
-        self.report_modifier(
-            [std::ops::AddAssign::add_assign,std::ops::AddAssign::add_assign],
-            [20, 5]
-        )      
-    }
 
-    fn report_modifier<F>(mut self, modifiers: [F; 2], modifier_values: [isize; 2]) -> Self
-    where
-        F: Fn(&mut isize, isize)
-    {
-        let target: _ = &self.evaluation
-            .target
-            .borrow();
-        let others: &Vec<grid::PointIndex> = self.report.as_inner();
-
-        // measures whether the selected tile will
-        // bring the agent closer to another agent
-        // whose location was revealed in the report
-        // and applies the specified effect
-        for other in others.iter() {
-            let oracle: _ = self.evaluation.agent.as_grid();
-            let active_oracle: _ = oracle.borrow();
-            let reported_position: _ = {
-                let (row,col): _ = other.as_dimensions();
-                active_oracle[row][col].borrow()
+        for report in self.reports.iter() {
+            let rewards: [isize; 2] = if report.is(&agents::ReportKind::Allies) {
+                [20, 5]
+            } else if self.outnumbers_zombies() {
+                [30, 10]
+            } else {
+                [-20, -10]
             };
-            let distance: _ = active_oracle.toroidal_distance_between(&target, &reported_position);
-
-            if &target.get_idx() == other {
-                modifiers[0](&mut self.evaluation.score, modifier_values[0]);
-            } else if distance < self.benchmark_distance {
-                modifiers[1](&mut self.evaluation.score, modifier_values[1]);
+            for other in report.as_inner().iter() {
+                if self.target == other {
+                    self.score += rewards[0]
+                } else if active_oracle.distance(self.target, other) < active_oracle.distance(&agent_idx, other) {
+                    self.score += rewards[1]
+                }
             }
-        } 
+        }
         self
+    } 
+
+    fn outnumbers_zombies(&self) -> bool {
+        let observer: _ = self.agent.as_observer().borrow();
+        observer.human_count > observer.zombie_count/4
     }
 }
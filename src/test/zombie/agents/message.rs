@@ -1,4 +1,5 @@
-use crate::context::grid;
+use crate::{context, message};
+use context::grid;
 use std::fmt;
 
 #[derive(Debug,Clone)]
@@ -7,6 +8,24 @@ pub enum Message {
     // Other
 }
 
+impl message::Measure for Message {
+    fn label(&self) -> &'static str {
+        match self {
+            Message::Report(report) if report.is(&ReportKind::Allies) => "Report(Allies)",
+            Message::Report(_) => "Report(Enemies)"
+        }
+    }
+
+    // the kind of report and the points it lists
+    fn size(&self) -> Option<usize> {
+        match self {
+            Message::Report(report) => Some(
+                std::mem::size_of::<ReportKind>() + report.as_inner().len() * std::mem::size_of::<grid::PointIndex>()
+            )
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Report {
     kind: ReportKind,
@@ -42,9 +42,9 @@ pub trait DeadOrAlive: GetPackage
             if self.is_alive() {
                 package.as_life_switch().set_dead()
             }
+            let id: usize = package.get_id();
             package.as_tile()
-                .borrow_mut()
-                .replace(crate::context::grid::PointState::Empty);
+                .release_where(|other| other.get_id() == id);
             
             package.as_observer()
                 .borrow_mut()
@@ -10,7 +10,7 @@ use std::{fmt, cell};
 
 #[derive(Clone)]
 pub struct Package {
-    agent: agent::Agent<usize,Message,Kind,Self>,
+    agent: agent::Agent<usize,Message,Kind,context::grid::Grid<Self>>,
     stats: Statistics,
     life_switch: LifeSwitch,
     observer: Cell<Observer>
@@ -19,14 +19,14 @@ pub struct Package {
 impl Package {
     pub fn new(
         comm: message::MessageInterface<usize,Message>, 
-        position: context::GridPosition<Self>,
+        position: Cell<context::GridPosition<Self>>,
         kind: Kind,
         id: usize,
         observer: Cell<Observer>
     ) -> Self {
         Package {
             stats: Statistics::random(&kind),
-            agent: agent::Agent::new(comm,position.into_cell(),kind,id),
+            agent: agent::Agent::new(comm,position,kind,id),
             observer,
             life_switch: LifeSwitch::new(),
         }
@@ -39,7 +39,7 @@ impl Package {
 
     #[inline]
     pub fn as_grid(&self) -> utils::Cell<context::grid::Grid<Self>> {
-        self.agent.as_grid()
+        self.agent.as_space()
     }
 
     #[inline]
@@ -5,9 +5,12 @@ use crate::test::zombie::agents;
 use agents::{package, actions, GetPackage};
 use actions::{interaction};
 use interaction::{communication,restoration,skirmish};
+use actions::field;
 
 use std::{fmt};
 
+use log::*;
+
 pub struct Human {
     kind: agents::Kind,
     package: package::Package
@@ -24,23 +27,23 @@ impl agents::Agent for Human {
 impl agents::Action for Human {
     fn act(&mut self) 
     {
-        let selected_tile: _ = self.select_tile().expect("could not select a tile");
-            
-        if selected_tile.borrow()
-            .state()
-            .is_empty() 
-        {
-            self.walk_to(selected_tile)
-        } else {
-            let other: _ = &selected_tile.borrow()
-                .state()
-                .as_inner()
-                .unwrap()
-                .clone();
-            self.interact_with(other);
+        // walled in on every side, the human stays put and forages
+        if let Some(selected_tile) = self.select_tile() {
+            if selected_tile.has_room() {
+                self.walk_to(selected_tile)
+            } else {
+                let others: Vec<package::Package> = selected_tile
+                    .state()
+                    .occupants()
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                for other in others.iter() {
+                    self.interact_with(other);
+                }
+            }
         }
-
-
+        self.forage();
     }
 }
 
@@ -74,7 +77,12 @@ impl Human {
     }
 
     fn walk_to(&mut self, new: context::GridPosition<package::Package>) {
-        actions::movement::Movement::new(self, new).step()
+        actions::movement::Movement::new(self, new).step();
+        field::Scent::new(&self.package).leave_trail()
+    }
+
+    fn forage(&self) {
+        field::Forage::new(&self.package).eat()
     }
 
     fn communicate_with(&self, other: &package::Package) {
@@ -85,12 +93,15 @@ impl Human {
             .find_enemies()
             .prepare();
 
-        self.package
+        let sent: Result<_,_> = self.package
             .as_messenger()
             .agent_outbox
             .borrow_mut()
-            .try_send()
-            .expect("could not send message");
+            .try_send();
+
+        if let Err(error) = sent {
+            warn!("{} could not deliver every message: {}", self.package.get_id(), error)
+        }
     }
 
     fn give_health_to(&self, other: &package::Package) {
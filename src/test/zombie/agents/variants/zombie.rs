@@ -21,16 +21,23 @@ impl agents::Agent for Zombie {
 
 impl agents::Action for Zombie {
     fn act(&mut self) {
-        let selected_tile: _ = self.select_tile().expect("could not select a tile");
-        if selected_tile.borrow().state().is_empty() {
+        // walled in on every side, the zombie waits where it is
+        let selected_tile: _ = match self.select_tile() {
+            Some(tile) => tile,
+            None => return
+        };
+        if selected_tile.has_room() {
             self.walk_to(selected_tile)
         } else {
-            let other: _ = &selected_tile.borrow()
+            let others: Vec<package::Package> = selected_tile
                 .state()
-                .as_inner()
-                .unwrap()
-                .clone();
-            self.interact_with(other);
+                .occupants()
+                .into_iter()
+                .cloned()
+                .collect();
+            for other in others.iter() {
+                self.interact_with(other);
+            }
         }
     }
 }
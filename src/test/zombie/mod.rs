@@ -41,6 +41,13 @@ fn zombie_test_main()
                 }
             }
         }
+        world::EnvironmentFactory::tick(&env);
+    }
+
+    if let Some(traffic) = world::EnvironmentFactory::traffic(&env) {
+        for (label, counts) in traffic.labels().iter() {
+            warn!("{}: {:?}", label, counts);
+        }
     }
 }
 
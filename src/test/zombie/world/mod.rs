@@ -1,4 +1,5 @@
-use crate::{environment,message,context};
+use crate::{environment,message,context,utils};
+use utils::Cell;
 
 use environment::Population;
 use context::grid;
@@ -7,12 +8,19 @@ mod seed;
 use seed::{spawn,feature};
 
 use super::{agents};
-use agents::{Kind, Agent};
+use agents::{Kind, Agent, actions};
+use actions::field;
 
 use simplelog;
 use log::*;
 use std::fs;
 
+/// How many points a human's voice carries.
+const EARSHOT: f64 = 8.0;
+
+/// Seeds where the agents are placed, so that a run can be replayed.
+const SEED: u64 = 2020;
+
 pub struct EnvironmentFactory;
 
 impl EnvironmentFactory {
@@ -29,6 +37,7 @@ impl EnvironmentFactory {
             .expect("could not generate populated environment");
 
         Self::occupy_positions(&mut environment);
+        Self::refresh_hunt(&environment);
 
         environment
     }
@@ -52,14 +61,29 @@ impl EnvironmentFactory {
 
     fn build_config() -> environment::Configuration<Kind,Box<dyn Agent>> {
         let feature_seed: feature::FeatureSeed<_,_,_> = {
-            let addresses: _ = message::AddressCollection::default().into_cell();
+            // a busy inbox gets a few more turns, further and further apart, before its message is given up on
+            let delivery: message::Delivery = message::Delivery {
+                retry: message::RetryPolicy { requeues: 3, backoff_ticks: 1 },
+                dead_letters: true,
+                ..Default::default()
+            };
+            let addresses: _ = message::AddressCollection::default()
+                .with_delivery(delivery)
+                .measured()
+                .into_cell();
             let message_seed: _ = feature::MessageSeed::new(addresses);
             
             info!("Spawning Grid...");
-            let grid: _ = context::grid::Grid::new(1000,1000).into_cell();
-            let grid_seed: _ = feature::GridSeed::new(grid);
-
-            feature::FeatureSeed::new(message_seed, grid_seed)
+            let mut grid: _ = context::grid::Grid::new(1000,1000);
+            Self::build_fields(&mut grid);
+            let grid: _ = grid.into_cell();
+            let grid_seed: _ = feature::GridSeed::new(grid, grid::Placement::Uniform, SEED)
+                .expect("a uniform placement fits any grid");
+
+            let feature_seed: feature::FeatureSeed<_,_,_> = feature::FeatureSeed::new(message_seed, grid_seed);
+            let range: _ = Self::earshot(feature_seed.as_whereabouts().clone());
+            feature_seed.as_addresses().borrow_mut().set_range(range);
+            feature_seed
         };
 
         let human_spawn_seed: _ = spawn::SpawnSeed::new(Kind::Human, 20000);
@@ -83,14 +107,106 @@ impl EnvironmentFactory {
         config
     }
 
+    // a shout carries a few points across open ground, but not through walls
+    fn earshot(whereabouts: feature::Whereabouts<usize,agents::package::Package>) -> message::Range<usize> 
+    {
+        let sight: feature::Whereabouts<_,_> = whereabouts.clone();
+        message::Range::new(EARSHOT, move |sender_id, recipient_id| {
+            let whereabouts: std::cell::Ref<_> = whereabouts.borrow();
+            let (sender, recipient): _ = (whereabouts.get(sender_id)?, whereabouts.get(recipient_id)?);
+            let distance: grid::Distance = sender.borrow().distance_to(&recipient.borrow());
+            Some(distance.as_f64())
+        })
+        .with_line_of_sight(move |sender_id, recipient_id| {
+            let whereabouts: std::cell::Ref<_> = sight.borrow();
+            match (whereabouts.get(sender_id), whereabouts.get(recipient_id)) {
+                (Some(sender), Some(recipient)) => sender.borrow().in_sight_of(&recipient.borrow()),
+                _ => true
+            }
+        })
+        .silently()
+    }
+
+    fn build_fields(grid: &mut grid::Grid<agents::package::Package>) {
+        grid.add_field(field::SCENT, grid::FieldValue::Scalar(0.0));
+        grid.add_dynamics(field::SCENT, grid::Dynamics::diffusion(0.05));
+        grid.add_dynamics(field::SCENT, grid::Dynamics::decay(0.1));
+
+        grid.add_field(field::FOOD, grid::FieldValue::Scalar(1.0));
+        grid.add_dynamics(field::FOOD, grid::Dynamics::Regrowth { rate: 0.01, ceiling: 1.0 });
+
+        grid.add_flow(field::HUNT, grid::Obstacles::default());
+    }
+
+    /// Advances the shared world state once every agent has had a turn.
+    pub fn tick(environment: &environment::Environment<Kind,Box<dyn Agent>>) {
+        if let Some(grid) = Self::shared_grid(environment) {
+            grid.borrow_mut().update_fields()
+        }
+        Self::log_traffic(environment);
+        Self::deliver_arrivals(environment);
+        Self::refresh_hunt(environment)
+    }
+
+    /// Everything the agents' messages have added up to so far.
+    pub fn traffic(environment: &environment::Environment<Kind,Box<dyn Agent>>) -> Option<message::Traffic<usize>> {
+        Self::shared_addresses(environment).map(|addresses| addresses.borrow().traffic().clone())
+    }
+
+    // what the report exchange cost over the tick that is ending
+    fn log_traffic(environment: &environment::Environment<Kind,Box<dyn Agent>>) 
+    {
+        if let Some(addresses) = Self::shared_addresses(environment) {
+            let addresses: std::cell::Ref<message::AddressCollection<_,_>> = addresses.borrow();
+            info!("Traffic on tick {}: {:?}", addresses.get_clock(), addresses.traffic().tick(addresses.get_clock()))
+        }
+    }
+
+    // releases the messages that have been in transit long enough
+    fn deliver_arrivals(environment: &environment::Environment<Kind,Box<dyn Agent>>) 
+    {
+        let addresses: Option<Cell<message::AddressCollection<usize,agents::Message>>> = Self::shared_addresses(environment);
+
+        if let Some(Err(error)) = addresses.map(|addresses| addresses.borrow_mut().advance()) {
+            warn!("Some messages could not be delivered: {}", error)
+        }
+    }
+
+    fn shared_addresses(environment: &environment::Environment<Kind,Box<dyn Agent>>) -> Option<Cell<message::AddressCollection<usize,agents::Message>>> {
+        environment.iter()
+            .flat_map(|(_, agents)| agents.iter())
+            .next()
+            .map(|agent| agent.get_package().as_messenger().agent_outbox.borrow().get_addresses())
+    }
+
+    // points the zombies' flow field at wherever the living humans now stand
+    fn refresh_hunt(environment: &environment::Environment<Kind,Box<dyn Agent>>) 
+    {
+        let humans: Vec<grid::PointIndex> = environment.iter()
+            .flat_map(|(_, agents)| agents.iter())
+            .filter(|agent| agent.get_kind().is(&Kind::Human) && agent.is_alive())
+            .map(|agent| agent.get_package().as_tile().get_idx())
+            .collect();
+
+        if let Some(grid) = Self::shared_grid(environment) {
+            grid.borrow_mut().refresh_flow(field::HUNT, humans)
+        }
+    }
+
+    fn shared_grid(environment: &environment::Environment<Kind,Box<dyn Agent>>) -> Option<Cell<grid::Grid<agents::package::Package>>> {
+        environment.iter()
+            .flat_map(|(_, agents)| agents.iter())
+            .next()
+            .map(|agent| agent.get_package().as_grid())
+    }
+
     fn occupy_positions(environment: &mut environment::Environment<Kind,Box<dyn Agent>>) {
         for (_, agents) in environment.iter_mut() {
             for agent in agents.iter_mut() {
                 let agent_package: _ = agent.get_package();
                 agent_package.as_tile()
-                    .borrow_mut()
                     .replace(grid::PointState::Occupied(agent_package.clone()));
-                info!("SpawnPosition: {:?}", agent_package.as_tile().borrow())
+                info!("SpawnPosition: {:?}", agent_package.as_tile())
             }      
         }
     }
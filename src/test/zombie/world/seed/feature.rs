@@ -2,14 +2,17 @@ use super::{Cell, message, context, grid};
 use crate::test::zombie::agents;
 use agents::package;
 
-use rand::Rng;
-
+use rand::{SeedableRng, rngs};
 use log::*;
 
+/// Where every seeded agent stands, kept up to date as they move.
+pub type Whereabouts<I,T> = Cell<std::collections::HashMap<I, Cell<context::GridPosition<T>>>>;
+
 pub struct FeatureSeed<I,M,T> {
     message: MessageSeed<I,M>,
     grid_seed: GridSeed<T>,
-    observer: ObserverSeed
+    observer: ObserverSeed,
+    whereabouts: Whereabouts<I,T>
 }
 
 impl<I,M,T> Clone for FeatureSeed<I,M,T> {
@@ -17,7 +20,8 @@ impl<I,M,T> Clone for FeatureSeed<I,M,T> {
         Self {
             message: self.message.clone(),
             grid_seed: self.grid_seed.clone(),
-            observer: self.observer.clone()
+            observer: self.observer.clone(),
+            whereabouts: self.whereabouts.clone()
         }
     }
 }
@@ -33,22 +37,28 @@ where
         Self {
             message: message_seed,
             grid_seed,
-            observer: ObserverSeed::new()
+            observer: ObserverSeed::new(),
+            whereabouts: std::rc::Rc::new(std::cell::RefCell::new(std::collections::HashMap::new()))
         }
     }
 
+    pub fn as_addresses(&self) -> &Cell<message::AddressCollection<I,M>> {
+        &self.message.0
+    }
+
+    pub fn as_whereabouts(&self) -> &Whereabouts<I,T> {
+        &self.whereabouts
+    }
+
     pub fn new_communicator(&self, id: I) -> message::MessageInterface<I,M> {
         let addresses: _ = self.message.0.clone();
         message::MessageInterface::new(id, addresses)
     }
 
-    pub fn new_position(&self) -> context::GridPosition<T> {
-        let total_points: usize = self.grid_seed.row*self.grid_seed.col;
-        if self.grid_seed.occupied_points < total_points {
-            let point: _ = self.grid_seed.unchecked_new_point();
-            let grid: _ = self.grid_seed.grid.clone();
-            context::GridPosition::new(point,grid)
-        } else { unimplemented!() }            
+    pub fn new_position(&self) -> Result<context::GridPosition<T>, grid::PlacementError> {
+        let idx: grid::PointIndex = self.grid_seed.new_point()?;
+        let grid: _ = self.grid_seed.grid.clone();
+        Ok(context::GridPosition::new(idx,grid))
     }
 
     pub fn new_observer(&self, kind: &agents::Kind) -> Cell<package::Observer> {
@@ -74,48 +84,38 @@ impl<I,M> MessageSeed<I,M> {
 }
 
 pub struct GridSeed<T> {
-    row: usize,
-    col: usize,
-    occupied_points: usize,
-    grid: Cell<grid::Grid<T>>
+    grid: Cell<grid::Grid<T>>,
+    spawner: Cell<grid::Spawner>,
+    rng: Cell<rngs::StdRng>
 }
 
 impl<T> Clone for GridSeed<T> {
     fn clone(&self) -> Self {
         Self {
-            row: self.row,
-            col: self.col,
-            occupied_points: self.occupied_points,
-            grid: self.grid.clone()
+            grid: self.grid.clone(),
+            spawner: self.spawner.clone(),
+            rng: self.rng.clone()
         }
     }
 }
 
 impl<T> GridSeed<T> {
-    pub fn new(grid: Cell<grid::Grid<T>>) -> Self {
-        let (row,col): _ = grid.borrow().get_dimensions();
-        Self {
-            occupied_points: 0,
-            row,
-            col,
+    /// Places agents by `placement`, drawing from a generator seeded with `seed` so that the 
+    /// same seed lays the world out the same way.
+    pub fn new(grid: Cell<grid::Grid<T>>, placement: grid::Placement, seed: u64) -> Result<Self, grid::PlacementError> {
+        let spawner: grid::Spawner = grid::Spawner::new(&grid.borrow(), placement)?;
+        Ok(Self {
             grid,
-        }
+            spawner: std::rc::Rc::new(std::cell::RefCell::new(spawner)),
+            rng: std::rc::Rc::new(std::cell::RefCell::new(rngs::StdRng::seed_from_u64(seed)))
+        })
     }
 
-    // potential infinite loop if not checked before calling
-    fn unchecked_new_point(&self) -> Cell<grid::Point<T>> {
-        loop {
-            let rng_row: usize = rand::thread_rng().gen_range(0, self.row);
-            let rng_col: usize = rand::thread_rng().gen_range(0, self.col);
-
-            let src_point: _ = self.grid.borrow();
-            let mut try_point: std::cell::RefMut<_> = src_point[rng_row][rng_col].borrow_mut();
-
-            if try_point.state().is_empty() {    
-                try_point.replace(grid::PointState::Claimed);
-                return src_point[rng_row][rng_col].clone()
-            }
-        }
+    // claims a free point so no other seed can hand it out
+    fn new_point(&self) -> Result<grid::PointIndex, grid::PlacementError> {
+        self.spawner
+            .borrow_mut()
+            .place(&mut self.grid.borrow_mut(), &mut *self.rng.borrow_mut())
     }
 }
 
@@ -21,18 +21,32 @@ impl environment::Seed for EnvironmentSeed
     type Output = Box<dyn Agent>;
     type Kind = Kind;
 
-    fn seed(&mut self) -> Self::Output {
+    fn seed(&mut self) -> Option<Self::Output> {
+        let position: Cell<context::GridPosition<_>> = match self.feature.new_position() {
+            Ok(position) => position.into_cell(),
+            Err(error) => {
+                warn!("Could not place a {:?}: {}", self.kind(), error);
+                return None
+            }
+        };
         let id: usize = self.spawn.new_id();
         let kind: Kind = self.kind();
 
         let comm: message::MessageInterface<_,_> = self.feature.new_communicator(id);
-        let position: context::GridPosition<_> = self.feature.new_position();
         let observer: Cell<package::Observer> = self.feature.new_observer(&kind);
 
+        // lets kind and spatial broadcasts find the agent wherever it goes
+        let locator: _ = context::GridPosition::locator(position.clone(), package::Package::get_id);
+        let mut addresses: std::cell::RefMut<_> = self.feature.as_addresses().borrow_mut();
+        addresses.set_kind(id, format!("{:?}", kind));
+        addresses.set_locator(id, locator);
+        drop(addresses);
+        self.feature.as_whereabouts().borrow_mut().insert(id, position.clone());
+
         let package: _ = package::Package::new(comm, position, kind.clone(),id, observer);
 
         info!("\nSeeding agent: {}", id);
-        EnvironmentSeed::seed_agent(kind, package)
+        Some(EnvironmentSeed::seed_agent(kind, package))
     }
 
     fn kind(&self) -> Kind {
@@ -46,23 +46,14 @@ impl<T> GreedyLock for rc::Rc<cell::RefCell<T>>
 
     fn await_greedy_lock_with_timeout<'a:'b,'b>(&'a self, timeout_limit: time::Duration) -> Result<cell::RefMut<'b,Self::Output>, cell::BorrowMutError>
     {
-        let mut unsafe_initialised_mem: Result<cell::RefMut<'b,T>, cell::BorrowMutError> = unsafe { std::mem::zeroed() };
         let timer_start_point: time::Instant = time::Instant::now();
-        
-        while timer_start_point.elapsed() < timeout_limit 
-        {
-            match self.try_borrow_mut() {
-                Ok(inner) => {
-                    unsafe_initialised_mem = Ok(inner);
-                    break
-                },
-                Err(e) => {
-                    unsafe_initialised_mem = Err(e);
-                    continue
-                }
-            }
+
+        // tried at least once, so that a zero timeout still gets an answer
+        let mut attempt: Result<cell::RefMut<'b,T>, cell::BorrowMutError> = self.try_borrow_mut();
+        while attempt.is_err() && timer_start_point.elapsed() < timeout_limit {
+            attempt = self.try_borrow_mut()
         }
-        unsafe_initialised_mem
+        attempt
     }
 }
 
@@ -87,23 +78,14 @@ impl<'a, T> GreedyLock for &'a rc::Rc<cell::RefCell<T>>
 
     fn await_greedy_lock_with_timeout<'b:'c,'c>(&'b self, timeout_limit: time::Duration) -> Result<cell::RefMut<'c,Self::Output>, cell::BorrowMutError>
     {
-        let mut unsafe_initialised_mem: Result<cell::RefMut<'c,T>, cell::BorrowMutError> = unsafe { std::mem::zeroed() };
         let timer_start_point: time::Instant = time::Instant::now();
-        
-        while timer_start_point.elapsed() < timeout_limit 
-        {
-            match self.try_borrow_mut() {
-                Ok(inner) => {
-                    unsafe_initialised_mem = Ok(inner);
-                    break
-                },
-                Err(e) => {
-                    unsafe_initialised_mem = Err(e);
-                    continue
-                }
-            }
+
+        // tried at least once, so that a zero timeout still gets an answer
+        let mut attempt: Result<cell::RefMut<'c,T>, cell::BorrowMutError> = self.try_borrow_mut();
+        while attempt.is_err() && timer_start_point.elapsed() < timeout_limit {
+            attempt = self.try_borrow_mut()
         }
-        unsafe_initialised_mem
+        attempt
     }
 }
 
@@ -128,23 +110,14 @@ impl<T> GreedyBorrow for rc::Rc<cell::RefCell<T>>
 
     fn await_greedy_borrow_with_timeout<'a:'b,'b>(&'a self, timeout_limit: time::Duration) -> Result<cell::Ref<'b,Self::Output>, cell::BorrowError>
     {
-        let mut unsafe_initialised_mem: Result<cell::Ref<'b,T>, cell::BorrowError> = unsafe { std::mem::zeroed() };
         let timer_start_point: time::Instant = time::Instant::now();
-        
-        while timer_start_point.elapsed() < timeout_limit 
-        {
-            match self.try_borrow() {
-                Ok(inner) => {
-                    unsafe_initialised_mem = Ok(inner);
-                    break
-                },
-                Err(e) => {
-                    unsafe_initialised_mem = Err(e);
-                    continue
-                }
-            }
+
+        // tried at least once, so that a zero timeout still gets an answer
+        let mut attempt: Result<cell::Ref<'b,T>, cell::BorrowError> = self.try_borrow();
+        while attempt.is_err() && timer_start_point.elapsed() < timeout_limit {
+            attempt = self.try_borrow()
         }
-        unsafe_initialised_mem
+        attempt
     }
 } 
 